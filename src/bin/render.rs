@@ -0,0 +1,272 @@
+//! Renders a still of the Mandelbrot set, or its Buddhabrot, to a PNG on the CPU
+//!
+//! Views are described the same way as in the web viewer, so the same centre and zoom give the same picture
+
+use std::{env, fs, fs::File, io::BufWriter, process::ExitCode};
+
+use mandlebrot::{
+    cpu::{render_with, Buddhabrot},
+    fixed::{precision_for_scale, Fixed},
+    palette::{builtin, Format, Gradient, Mapping, Palette},
+    view::View,
+};
+
+const USAGE: &str = "Usage: render [--center RE,IM] [--zoom ZOOM] [--rotation RADIANS] [--size WIDTHxHEIGHT] [--depth DEPTH] [--colouring banded|smooth] [--palette NAME|FILE] [--buddhabrot SAMPLES_PER_PIXEL [--depths RED,GREEN,BLUE] [--exposure EXPOSURE] [--gamma GAMMA]] OUTPUT.png";
+
+#[derive(Clone, Debug, PartialEq)]
+struct Options {
+    center: (f64, f64),
+    /// Magnification relative to the initial view of the web viewer
+    zoom: f64,
+    rotation: f64,
+    size: (usize, usize),
+    depth: u32,
+    /// Whether to colour by the continuous iteration count rather than whole iterations
+    smooth: bool,
+    /// One of the built-in palettes, see `palette::BUILTIN_PALETTES`, or a `.map`, `.ugr` or `.ggr` file
+    palette: String,
+    /// Samples per pixel to trace for a Buddhabrot, instead of rendering the set itself
+    buddhabrot: Option<u64>,
+    /// Iteration limits of the red, green and blue channels of the Buddhabrot, all `depth` if not given
+    depths: Option<[u32; 3]>,
+    exposure: f64,
+    gamma: f64,
+    output: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            center: (0.0, 0.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            size: (1920, 1080),
+            depth: 1000,
+            smooth: false,
+            palette: String::from("Classic"),
+            buddhabrot: None,
+            depths: None,
+            exposure: 1.0,
+            gamma: 2.0,
+            output: String::new(),
+        }
+    }
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str, separator: char) -> Option<(T, T)> {
+    let (a, b) = value.split_once(separator)?;
+    Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+}
+
+/// Number which isn't infinite or `NaN`
+fn parse_finite(value: &str) -> Option<f64> {
+    value.parse().ok().filter(|number: &f64| number.is_finite())
+}
+
+/// Number above 0 which isn't infinite or `NaN`
+fn parse_positive(value: &str) -> Option<f64> {
+    parse_finite(value).filter(|number| *number > 0.0)
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut output = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            output = Some(arg);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {arg}"))?;
+        let invalid = || format!("Invalid value for {arg}: {value}");
+        match arg.as_str() {
+            "--center" => {
+                options.center = parse_pair(&value, ',')
+                    .filter(|(re, im): &(f64, f64)| re.is_finite() && im.is_finite())
+                    .ok_or_else(invalid)?
+            }
+            "--zoom" => options.zoom = parse_positive(&value).ok_or_else(invalid)?,
+            "--rotation" => options.rotation = parse_finite(&value).ok_or_else(invalid)?,
+            "--size" => {
+                options.size = parse_pair(&value, 'x')
+                    .filter(|&(width, height)| width > 0 && height > 0)
+                    .ok_or_else(invalid)?
+            }
+            "--depth" => options.depth = value.parse().map_err(|_| invalid())?,
+            "--colouring" => {
+                options.smooth = match value.as_str() {
+                    "banded" => false,
+                    "smooth" => true,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--palette" => {
+                if Format::from_file_name(&value).is_none() {
+                    builtin(&value).ok_or_else(invalid)?;
+                }
+                options.palette = value;
+            }
+            "--buddhabrot" => options.buddhabrot = Some(value.parse().map_err(|_| invalid())?),
+            "--depths" => {
+                let depths: Vec<u32> = value
+                    .split(',')
+                    .map(|depth| depth.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid())?;
+                options.depths = Some(depths.try_into().map_err(|_| invalid())?);
+            }
+            "--exposure" => options.exposure = parse_positive(&value).ok_or_else(invalid)?,
+            "--gamma" => options.gamma = parse_positive(&value).ok_or_else(invalid)?,
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+    options.output = output.ok_or("Missing output path")?;
+    Ok(options)
+}
+
+impl Options {
+    fn view(&self) -> View {
+        let scale = 1.0 / self.zoom;
+        let precision = precision_for_scale(scale);
+        View {
+            center: (
+                Fixed::from_f64(self.center.0, precision),
+                Fixed::from_f64(self.center.1, precision),
+            ),
+            scale,
+            rotation: self.rotation,
+        }
+    }
+}
+
+/// Reads the gradient `--palette` names, which is a file if it has the extension of one of the palette formats
+fn read_palette(name: &str) -> Result<Gradient, String> {
+    match Format::from_file_name(name) {
+        Some(format) => format.parse(&fs::read_to_string(name).map_err(|error| error.to_string())?),
+        None => builtin(name).ok_or_else(|| format!("Unknown palette {name}")),
+    }
+}
+
+fn write_png(options: &Options, pixels: &[u8]) -> Result<(), String> {
+    let file = File::create(&options.output).map_err(|error| error.to_string())?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        options.size.0 as u32,
+        options.size.1 as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(|error| error.to_string())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let (width, height) = options.size;
+    let aspect = height as f64 / width as f64;
+    let view = options.view();
+    let gradient = match read_palette(&options.palette) {
+        Ok(gradient) => gradient,
+        Err(error) => {
+            eprintln!("Couldn't read palette {}: {error}", options.palette);
+            return ExitCode::FAILURE;
+        }
+    };
+    let pixels = match options.buddhabrot {
+        Some(samples) => {
+            let depths = options.depths.unwrap_or([options.depth; 3]);
+            let total = (width as u64)
+                .checked_mul(height as u64)
+                .and_then(|pixels| pixels.checked_mul(samples))
+                .and_then(|total| usize::try_from(total).ok());
+            let Some(total) = total else {
+                eprintln!("Too many samples for a {width}x{height} image\n{USAGE}");
+                return ExitCode::FAILURE;
+            };
+            let mut buddhabrot = Buddhabrot::new(width, height, depths);
+            buddhabrot.accumulate(total, &view, aspect);
+            buddhabrot.image(options.exposure, options.gamma)
+        }
+        None => {
+            let palette = Palette::new(gradient, Mapping::default());
+            render_with(
+                width,
+                height,
+                options.depth,
+                options.smooth,
+                &palette,
+                |point| view.point_at(point, aspect),
+            )
+        }
+    };
+    if let Err(error) = write_png(&options, &pixels) {
+        eprintln!("Couldn't write {}: {error}", options.output);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_args, Options};
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_options() {
+        assert_eq!(
+            parse_args(args(
+                "--center -0.75,0.1 --zoom 20 --size 640x480 --depth 500 --colouring smooth --palette fire out.png"
+            )),
+            Ok(Options {
+                center: (-0.75, 0.1),
+                zoom: 20.0,
+                size: (640, 480),
+                depth: 500,
+                smooth: true,
+                palette: String::from("fire"),
+                output: String::from("out.png"),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse_args(args(
+                "--buddhabrot 50 --depths 5000,500,50 --gamma 2.5 out.png"
+            )),
+            Ok(Options {
+                buddhabrot: Some(50),
+                depths: Some([5000, 500, 50]),
+                gamma: 2.5,
+                output: String::from("out.png"),
+                ..Default::default()
+            })
+        );
+        assert!(parse_args(args("--depths 5000,500 out.png")).is_err());
+        assert!(parse_args(args("--zoom 0 out.png")).is_err());
+        assert!(parse_args(args("--zoom inf out.png")).is_err());
+        assert!(parse_args(args("--center NaN,0 out.png")).is_err());
+        assert!(parse_args(args("--center 0,-inf out.png")).is_err());
+        assert!(parse_args(args("--rotation inf out.png")).is_err());
+        assert!(parse_args(args("--exposure 0 out.png")).is_err());
+        assert!(parse_args(args("--exposure NaN out.png")).is_err());
+        assert!(parse_args(args("--gamma -1 out.png")).is_err());
+        assert!(parse_args(args("--gamma inf out.png")).is_err());
+        assert!(parse_args(args("--colouring stripes out.png")).is_err());
+        assert!(parse_args(args("--palette plasma out.png")).is_err());
+        assert!(parse_args(args("--palette plasma.ggr out.png")).is_ok());
+        assert!(parse_args(args("--size 640 out.png")).is_err());
+        assert!(parse_args(args("--depth 500")).is_err());
+    }
+}
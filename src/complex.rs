@@ -0,0 +1,31 @@
+//! Complex numbers as `[re, im]` pairs, for the CPU ports of the shaders and the maths done before drawing
+
+pub type Complex = [f64; 2];
+
+pub fn add(a: Complex, b: Complex) -> Complex {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+pub fn sub(a: Complex, b: Complex) -> Complex {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+pub fn mul(a: Complex, b: Complex) -> Complex {
+    [a[0] * b[0] - a[1] * b[1], a[0] * b[1] + a[1] * b[0]]
+}
+
+pub fn scale(a: Complex, factor: f64) -> Complex {
+    [a[0] * factor, a[1] * factor]
+}
+
+pub fn div(a: Complex, b: Complex) -> Complex {
+    let norm = b[0] * b[0] + b[1] * b[1];
+    [
+        (a[0] * b[0] + a[1] * b[1]) / norm,
+        (a[1] * b[0] - a[0] * b[1]) / norm,
+    ]
+}
+
+pub fn norm(a: Complex) -> f64 {
+    a[0].hypot(a[1])
+}
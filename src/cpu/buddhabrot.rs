@@ -0,0 +1,196 @@
+use crate::view::View;
+
+/// Iteration limits of the red, green and blue channels for the plain Buddhabrot, which comes out grey
+pub const BUDDHABROT_DEPTHS: [u32; 3] = [1000; 3];
+
+/// Iteration limits of the red, green and blue channels for the Nebulabrot
+pub const NEBULABROT_DEPTHS: [u32; 3] = [5000, 500, 50];
+
+/// Density of the points visited by escaping orbits of the Mandelbrot set, accumulated a batch of samples at a time
+///
+/// Each channel only counts orbits which escape within its iteration limit in [`Buddhabrot::depths`]
+#[derive(Clone, Debug)]
+pub struct Buddhabrot {
+    pub width: usize,
+    pub height: usize,
+    pub depths: [u32; 3],
+    /// Visits to each pixel, top row first
+    counts: Vec<[u32; 3]>,
+    /// Number of `c` values traced so far
+    pub samples: u64,
+    /// State of the random number generator
+    seed: u64,
+    orbit: Vec<(f64, f64)>,
+}
+
+/// Whether `c` is in the main cardioid or the period 2 bulb, which make up most of the set but never escape
+fn in_main_components((x, y): (f64, f64)) -> bool {
+    let q = (x - 0.25).powi(2) + y * y;
+    q * (q + x - 0.25) <= 0.25 * y * y || (x + 1.0).powi(2) + y * y <= 1.0 / 16.0
+}
+
+/// Inverse of [`View::point_at`], going straight to the index of the pixel a point is in, if it's on the screen
+fn pixel_mapping(
+    view: &View,
+    aspect: f64,
+    width: usize,
+    height: usize,
+) -> impl Fn((f64, f64)) -> Option<usize> {
+    let center = (view.center.0.to_f64(), view.center.1.to_f64());
+    let (sin, cos) = view.rotation.sin_cos();
+    let scale = view.scale;
+    move |(re, im)| {
+        let (dx, dy) = ((re - center.0) / scale, (im - center.1) / scale);
+        let x = dx * cos + dy * sin;
+        let y = (dy * cos - dx * sin) / aspect;
+        let column = ((x + 1.0) / 2.0 * width as f64).floor();
+        let row = ((1.0 - y) / 2.0 * height as f64).floor();
+        ((0.0..width as f64).contains(&column) && (0.0..height as f64).contains(&row))
+            .then(|| row as usize * width + column as usize)
+    }
+}
+
+impl Buddhabrot {
+    pub fn new(width: usize, height: usize, depths: [u32; 3]) -> Self {
+        Self {
+            width,
+            height,
+            depths,
+            counts: vec![[0; 3]; width * height],
+            samples: 0,
+            seed: 0x9e37_79b9_7f4a_7c15,
+            orbit: Vec::new(),
+        }
+    }
+
+    /// Uniform random number from -2 to 2, using SplitMix64
+    fn random(&mut self) -> f64 {
+        self.seed = self.seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1_u64 << 53) as f64 * 4.0 - 2.0
+    }
+
+    /// Traces `samples` random values of `c` from the square which holds the whole set, adding the orbits of those
+    /// which escape to the pixels they pass through in `view`
+    ///
+    /// Orbits wander far from where they start, so the whole square is sampled however far the view is zoomed in
+    pub fn accumulate(&mut self, samples: usize, view: &View, aspect: f64) {
+        let to_pixel = pixel_mapping(view, aspect, self.width, self.height);
+        let max_depth = self.depths.into_iter().max().unwrap_or(0);
+        for _ in 0..samples {
+            let c = (self.random(), self.random());
+            self.samples += 1;
+            if in_main_components(c) {
+                continue;
+            }
+            self.orbit.clear();
+            let (mut re, mut im) = (0.0_f64, 0.0_f64);
+            let mut escaped = None;
+            for i in 0..max_depth {
+                (re, im) = (re * re - im * im + c.0, 2.0 * re * im + c.1);
+                if re * re + im * im > 4.0 {
+                    escaped = Some(i);
+                    break;
+                }
+                self.orbit.push((re, im));
+            }
+            let Some(escaped) = escaped else {
+                continue;
+            };
+            let channels = self.depths.map(|depth| escaped < depth);
+            for point in &self.orbit {
+                if let Some(index) = to_pixel(*point) {
+                    // Saturating, so the busiest pixels of a long render stay bright rather than wrapping round
+                    for (count, counted) in self.counts[index].iter_mut().zip(channels) {
+                        *count = count.saturating_add(counted as u32);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tone maps the counts into RGBA pixels, top row first
+    ///
+    /// Each channel is scaled so its busiest pixel is at `1 / exposure`, then raised to `1 / gamma`
+    pub fn image(&self, exposure: f64, gamma: f64) -> Vec<u8> {
+        let mut max = [0; 3];
+        for counts in &self.counts {
+            for (max, count) in max.iter_mut().zip(counts) {
+                *max = (*max).max(*count);
+            }
+        }
+        let mut pixels = Vec::with_capacity(self.counts.len() * 4);
+        for counts in &self.counts {
+            for (count, max) in counts.iter().zip(max) {
+                let value = (exposure * *count as f64 / max.max(1) as f64).min(1.0);
+                pixels.push((value.powf(1.0 / gamma) * 255.0).round() as u8);
+            }
+            pixels.push(255);
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cpu::buddhabrot::{in_main_components, pixel_mapping, Buddhabrot},
+        fixed::Fixed,
+        view::View,
+    };
+
+    #[test]
+    fn skips_main_components() {
+        assert!(in_main_components((0.0, 0.0)));
+        assert!(in_main_components((-1.0, 0.1)));
+        assert!(!in_main_components((0.3, 0.0)));
+        assert!(!in_main_components((-0.75, 0.2)));
+    }
+
+    #[test]
+    fn maps_points_back_to_pixels() {
+        let view = View {
+            center: (Fixed::from_f64(-0.5, 2), Fixed::from_f64(0.2, 2)),
+            scale: 1.5,
+            rotation: 0.3,
+        };
+        let (width, height) = (16, 12);
+        let to_pixel = pixel_mapping(&view, 0.75, width, height);
+        for (row, column) in [(0, 0), (3, 7), (11, 15)] {
+            // Middle of the pixel, as sampled by `render_with`
+            let x = (column as f64 + 0.5) / width as f64 * 2.0 - 1.0;
+            let y = 1.0 - (row as f64 + 0.5) / height as f64 * 2.0;
+            assert_eq!(
+                to_pixel(view.point_at((x, y), 0.75)),
+                Some(row * width + column)
+            );
+        }
+        assert_eq!(to_pixel(view.point_at((1.1, 0.0), 0.75)), None);
+    }
+
+    #[test]
+    fn channels_count_orbits_within_their_depth() {
+        let view = View {
+            center: (Fixed::from_f64(-0.5, 2), Fixed::zero(2)),
+            scale: 1.5,
+            rotation: 0.3,
+        };
+        let mut buddhabrot = Buddhabrot::new(64, 48, [2000, 200, 20]);
+        buddhabrot.accumulate(20000, &view, 0.75);
+        assert_eq!(buddhabrot.samples, 20000);
+        let totals = buddhabrot.counts.iter().fold([0; 3], |totals, counts| {
+            [0, 1, 2].map(|channel| totals[channel] + counts[channel])
+        });
+        // Every orbit counted by a channel is also counted by those with a larger limit
+        assert!(totals[0] >= totals[1] && totals[1] >= totals[2] && totals[2] > 0);
+        for counts in &buddhabrot.counts {
+            assert!(counts[0] >= counts[1] && counts[1] >= counts[2]);
+        }
+        let image = buddhabrot.image(1.0, 2.0);
+        assert_eq!(image.len(), 64 * 48 * 4);
+        assert_eq!(image.chunks(4).map(|pixel| pixel[0]).max(), Some(255));
+    }
+}
@@ -0,0 +1,273 @@
+//! CPU implementation of the escape-time renderer, following `shader.frag` and `colour.glsl`
+
+pub use buddhabrot::{Buddhabrot, BUDDHABROT_DEPTHS, NEBULABROT_DEPTHS};
+
+use crate::{
+    complex::{add, div, mul, norm, sub, Complex},
+    multibrot::SMOOTH_BAILOUT,
+    palette::Palette,
+};
+
+mod buddhabrot;
+
+/// Rectangle of the complex plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    /// Bottom left corner
+    pub min: (f64, f64),
+    /// Top right corner
+    pub max: (f64, f64),
+}
+
+/// Port of `escape_ratio()` in `escape.glsl`, for an orbit which first got beyond `radius` on iteration `i` with
+/// `|z| = magnitude`
+pub fn escape_ratio(
+    i: u32,
+    magnitude: f64,
+    radius: f64,
+    power: f64,
+    depth: u32,
+    smooth: bool,
+) -> f32 {
+    if !smooth {
+        return i as f32 / depth as f32;
+    }
+    let count = i as f64 + 1.0 - (magnitude.ln() / radius.ln()).ln() / power.ln();
+    (count.max(0.0) / depth as f64) as f32
+}
+
+/// Port of `pixel()` in `shader.frag`, in double precision
+///
+/// Returns the iteration `c` escaped at as a proportion of `depth`, or `1.0` if it didn't escape. With `smooth` the
+/// iteration is continuous, see [`escape_ratio`]
+pub fn pixel((x, y): (f64, f64), depth: u32, smooth: bool) -> f32 {
+    let radius = if smooth { SMOOTH_BAILOUT } else { 2.0 };
+    let (mut re, mut im) = (0.0_f64, 0.0_f64);
+    for i in 0..depth {
+        (re, im) = (re * re - im * im + x, 2.0 * re * im + y);
+        if re * re + im * im > radius * radius {
+            return escape_ratio(i, re.hypot(im), radius, 2.0, depth, smooth);
+        }
+    }
+    1.0
+}
+
+/// Port of the distance estimate `shader.frag` makes with `distance_estimate()` in `escape.glsl`
+///
+/// Returns the estimated distance in the plane from `c` to the Mandelbrot set, or `None` if it didn't escape within
+/// `depth` iterations
+pub fn distance_estimate((x, y): (f64, f64), depth: u32) -> Option<f64> {
+    let (mut re, mut im) = (0.0_f64, 0.0_f64);
+    // dz/dc, which goes to 2 z dz/dc + 1 each iteration
+    let (mut d_re, mut d_im) = (0.0_f64, 0.0_f64);
+    for _ in 0..depth {
+        (d_re, d_im) = (
+            2.0 * (re * d_re - im * d_im) + 1.0,
+            2.0 * (re * d_im + im * d_re),
+        );
+        (re, im) = (re * re - im * im + x, 2.0 * re * im + y);
+        let r = re.hypot(im);
+        if r > SMOOTH_BAILOUT {
+            return Some(0.5 * r * r.ln() / d_re.hypot(d_im));
+        }
+    }
+    None
+}
+
+/// Longest cycle [`interior`] looks for, like `MAX_PERIOD` in `shader.frag`
+const MAX_PERIOD: u32 = 64;
+/// How close an orbit has to come back to where it was to count as a cycle
+const CYCLE_TOLERANCE: f64 = 1.0e-4;
+/// Newton steps taken towards the exact point on a cycle before estimating the interior distance from it
+const NEWTON_STEPS: u32 = 4;
+
+/// Attracting cycle the orbit of a point inside the Mandelbrot set settles into
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cycle {
+    pub period: u32,
+    /// Derivative of the whole cycle with respect to z, whose magnitude goes from 0 at the centre of each hyperbolic
+    /// component to 1 at its edge
+    pub multiplier: Complex,
+    /// Estimated distance in the plane to the edge of the set
+    pub distance: f64,
+}
+
+/// Port of `find_cycle()` and `interior_distance()` in `shader.frag`, for the Mandelbrot set
+///
+/// Returns the cycle the orbit of `c` settled into after `depth` iterations, or `None` if it escaped or didn't come
+/// back round to itself within [`MAX_PERIOD`] more
+pub fn interior((x, y): (f64, f64), depth: u32) -> Option<Cycle> {
+    let step = |z: Complex| add(mul(z, z), [x, y]);
+    let twice = |[re, im]: Complex| [2.0 * re, 2.0 * im];
+    let mut z = [0.0, 0.0];
+    for _ in 0..depth {
+        z = step(z);
+        if norm(z) > 2.0 {
+            return None;
+        }
+    }
+    let start = z;
+    let mut multiplier = [1.0, 0.0];
+    let period = (1..=MAX_PERIOD).find(|_| {
+        multiplier = mul(twice(z), multiplier);
+        z = step(z);
+        norm(sub(z, start)) < CYCLE_TOLERANCE
+    })?;
+    // Newton's method on f^period(z) - z = 0 finds the point on the cycle exactly
+    z = start;
+    for _ in 0..NEWTON_STEPS {
+        let (mut w, mut dw) = (z, [1.0, 0.0]);
+        for _ in 0..period {
+            dw = mul(twice(w), dw);
+            w = step(w);
+        }
+        z = sub(z, div(sub(w, z), sub(dw, [1.0, 0.0])));
+    }
+    // Derivatives of the cycle with respect to z and c, and their second derivatives
+    let (mut dz, mut dc, mut dzdz, mut dzdc) = ([1.0, 0.0], [0.0, 0.0], [0.0, 0.0], [0.0, 0.0]);
+    for _ in 0..period {
+        dzdz = twice(add(mul(dz, dz), mul(z, dzdz)));
+        dzdc = twice(add(mul(dz, dc), mul(z, dzdc)));
+        dz = twice(mul(z, dz));
+        dc = add(twice(mul(z, dc)), [1.0, 0.0]);
+        z = step(z);
+    }
+    Some(Cycle {
+        period,
+        multiplier,
+        distance: (1.0 - norm(dz).powi(2))
+            / norm(add(dzdc, div(mul(dzdz, dc), sub([1.0, 0.0], dz)))),
+    })
+}
+
+/// Renders RGBA pixels, top row first, where `point` maps clip space coordinates to the complex plane
+pub fn render_with(
+    width: usize,
+    height: usize,
+    depth: u32,
+    smooth: bool,
+    palette: &Palette,
+    point: impl Fn((f64, f64)) -> (f64, f64),
+) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for row in 0..height {
+        // Sample the middle of each pixel, with y pointing up like `vPos`
+        let y = 1.0 - (row as f64 + 0.5) / height as f64 * 2.0;
+        for column in 0..width {
+            let x = (column as f64 + 0.5) / width as f64 * 2.0 - 1.0;
+            pixels.extend(palette.colour(pixel(point((x, y)), depth, smooth)));
+        }
+    }
+    pixels
+}
+
+/// Renders a rectangle of the complex plane into RGBA pixels, top row first
+pub fn render(
+    rect: &Rect,
+    width: usize,
+    height: usize,
+    depth: u32,
+    smooth: bool,
+    palette: &Palette,
+) -> Vec<u8> {
+    let half_width = (rect.max.0 - rect.min.0) / 2.0;
+    let half_height = (rect.max.1 - rect.min.1) / 2.0;
+    render_with(width, height, depth, smooth, palette, |(x, y)| {
+        (
+            rect.min.0 + (x + 1.0) * half_width,
+            rect.min.1 + (y + 1.0) * half_height,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        complex::norm,
+        cpu::{distance_estimate, interior, pixel, render, Rect},
+        palette::Palette,
+    };
+
+    #[test]
+    fn escape_ratio() {
+        assert_eq!(pixel((0.0, 0.0), 100, false), 1.0);
+        assert_eq!(pixel((-1.0, 0.0), 100, false), 1.0);
+        // 1 + i, then 1 + 3i
+        assert_eq!(pixel((1.0, 1.0), 100, false), 0.01);
+        assert_eq!(pixel((3.0, 0.0), 100, false), 0.0);
+    }
+
+    #[test]
+    fn smooth_escape_ratio() {
+        assert_eq!(pixel((-1.0, 0.0), 100, true), 1.0);
+        // 3, 12, 147, then 21612 is the first past 256
+        let count = 4.0 - (21612_f64.ln() / 256_f64.ln()).log2();
+        assert!((pixel((3.0, 0.0), 100, true) as f64 - count / 100.0).abs() < 1e-6);
+        // Bands are a whole iteration apart, but the continuous count barely changes between nearby points
+        let step = |smooth| {
+            (0..1000)
+                .map(|n| {
+                    let x = 0.3 + n as f64 * 1.0e-4;
+                    (pixel((x, 0.0), 100, smooth) - pixel((x + 1.0e-4, 0.0), 100, smooth)).abs()
+                })
+                .fold(0.0, f32::max)
+        };
+        assert!(step(false) >= 0.01);
+        assert!(step(true) < 0.002);
+    }
+
+    #[test]
+    fn distance_estimates() {
+        assert_eq!(distance_estimate((-1.0, 0.0), 100), None);
+        // The set is inside |c| <= 2 and reaches -2, so that's the nearest point to anything further along the real
+        // axis, and close to the set the estimate is between a quarter of the distance and the distance
+        for x in [-2.01, -2.1, -2.5] {
+            let distance = distance_estimate((x, 0.0), 100).unwrap();
+            let actual = -2.0 - x;
+            assert!(
+                distance >= actual / 4.0 && distance <= actual,
+                "{x}: {distance}"
+            );
+        }
+        // Estimates shrink along with the distance towards the cardioid from outside
+        let near = distance_estimate((0.0, 0.7), 1000).unwrap();
+        let far = distance_estimate((0.0, 1.5), 1000).unwrap();
+        assert!(near < far);
+    }
+
+    #[test]
+    fn interior_cycles() {
+        assert_eq!(interior((0.3, 0.0), 1000), None);
+        // 0 is the centre of the main cardioid, where 0 is a superattracting fixed point
+        let cycle = interior((0.0, 0.0), 100).unwrap();
+        assert_eq!(cycle.period, 1);
+        assert_eq!(cycle.multiplier, [0.0, 0.0]);
+        // The nearest point of the edge is the cusp at 0.25, and the estimate is between the distance and 4 times it
+        assert!(cycle.distance >= 0.25 && cycle.distance <= 1.0);
+        // The period 2 disc centred on -1, then the period 3 bulb at the top and the period 4 bulb to its left
+        for (c, period) in [((-1.0, 0.0), 2), ((-0.12, 0.74), 3), ((-1.31, 0.0), 4)] {
+            let cycle = interior(c, 1000).unwrap();
+            assert_eq!(cycle.period, period);
+            assert!(norm(cycle.multiplier) < 1.0);
+            assert!(cycle.distance > 0.0);
+        }
+        // The multiplier grows towards the edge of the disc at -0.75
+        let magnitude = |x| norm(interior((x, 0.0), 10000).unwrap().multiplier);
+        assert!(magnitude(-1.0) < magnitude(-0.9) && magnitude(-0.9) < magnitude(-0.8));
+    }
+
+    #[test]
+    fn render_orientation() {
+        // The top row escapes straight away and the bottom row is inside the set
+        let rect = Rect {
+            min: (-0.6, -2.0),
+            max: (0.4, 6.0),
+        };
+        let palette = Palette::default();
+        let pixels = render(&rect, 2, 2, 50, false, &palette);
+        assert_eq!(pixels.len(), 16);
+        assert_eq!(&pixels[0..8], &[0, 0, 0, 255, 0, 0, 0, 255]);
+        assert_eq!(&pixels[8..12], &palette.colour(1.0));
+        assert_eq!(&pixels[12..16], &palette.colour(1.0));
+    }
+}
@@ -0,0 +1,84 @@
+/// Iteration limit used before a view is zoomed in at all
+const BASE_DEPTH: f64 = 250.0;
+
+/// How quickly the automatic iteration limit grows with each factor of ten the view is zoomed in
+const DEPTH_GROWTH: f64 = 1.2;
+
+pub const MIN_DEPTH: u32 = 16;
+pub const MAX_DEPTH: u32 = 1 << 20;
+
+/// Iteration limit, either fixed or raised automatically as the view zooms in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Depth {
+    pub limit: u32,
+    pub auto: bool,
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self {
+            limit: 1000,
+            auto: true,
+        }
+    }
+}
+
+/// Iteration limit which keeps a view at `scale` detailed without wasting time on shallow views
+pub fn auto_depth(scale: f64) -> u32 {
+    let decades = (-scale.log10()).max(0.0);
+    ((BASE_DEPTH * (1.0 + decades).powf(DEPTH_GROWTH)) as u32).clamp(MIN_DEPTH, MAX_DEPTH)
+}
+
+impl Depth {
+    /// Fixed limit of `limit`, kept between [`MIN_DEPTH`] and [`MAX_DEPTH`]
+    pub fn fixed(limit: u32) -> Self {
+        Self {
+            limit: limit.clamp(MIN_DEPTH, MAX_DEPTH),
+            auto: false,
+        }
+    }
+
+    /// Iteration limit to use for a view at `scale`
+    pub fn for_scale(&self, scale: f64) -> u32 {
+        if self.auto {
+            auto_depth(scale)
+        } else {
+            self.limit
+        }
+    }
+
+    /// Multiplies the limit currently in use by `factor`, leaving automatic mode
+    pub fn multiply(&mut self, factor: f64, scale: f64) {
+        self.limit = ((self.for_scale(scale) as f64 * factor) as u32).clamp(MIN_DEPTH, MAX_DEPTH);
+        self.auto = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::depth::{auto_depth, Depth, MAX_DEPTH, MIN_DEPTH};
+
+    #[test]
+    fn auto_grows_with_zoom() {
+        assert!(auto_depth(1.0) < auto_depth(1e-5));
+        assert!(auto_depth(1e-5) < auto_depth(1e-50));
+        assert_eq!(auto_depth(10.0), auto_depth(1.0));
+    }
+
+    #[test]
+    fn multiply_leaves_auto() {
+        let mut depth = Depth::default();
+        depth.multiply(2.0, 1e-10);
+        assert!(!depth.auto);
+        assert_eq!(depth.limit, auto_depth(1e-10) * 2);
+        depth.multiply(0.0, 1.0);
+        assert_eq!(depth.for_scale(1e-10), 16);
+    }
+
+    #[test]
+    fn fixed_is_clamped() {
+        assert_eq!(Depth::fixed(500).for_scale(1e-10), 500);
+        assert_eq!(Depth::fixed(0).limit, MIN_DEPTH);
+        assert_eq!(Depth::fixed(u32::MAX).limit, MAX_DEPTH);
+    }
+}
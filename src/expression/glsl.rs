@@ -0,0 +1,236 @@
+use crate::expression::{Expression, Operator};
+
+/// Largest integer power expanded into repeated multiplication rather than going through logarithms
+const MAX_INTEGER_POWER: f64 = 64.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Type {
+    /// `float`, which is promoted to a complex number wherever one is needed
+    Real,
+    /// `vec2` of the real and imaginary parts
+    Complex,
+}
+
+/// GLSL source of a typed subexpression
+struct Typed {
+    source: String,
+    ty: Type,
+}
+
+impl Typed {
+    fn real(source: String) -> Self {
+        Self {
+            source,
+            ty: Type::Real,
+        }
+    }
+
+    fn complex(source: String) -> Self {
+        Self {
+            source,
+            ty: Type::Complex,
+        }
+    }
+
+    fn as_complex(&self) -> String {
+        match self.ty {
+            Type::Real => format!("vec2({}, 0.0)", self.source),
+            Type::Complex => self.source.clone(),
+        }
+    }
+}
+
+/// Functions of one complex argument, with the helper in `complex.glsl` which computes them
+const COMPLEX_FUNCTIONS: [(&str, &str); 10] = [
+    ("sin", "csin"),
+    ("cos", "ccos"),
+    ("tan", "ctan"),
+    ("sinh", "csinh"),
+    ("cosh", "ccosh"),
+    ("tanh", "ctanh"),
+    ("exp", "cexp"),
+    ("log", "clog"),
+    ("sqrt", "csqrt"),
+    ("conj", "cconj"),
+];
+
+/// Functions of one complex argument which give a real number
+const REAL_FUNCTIONS: [(&str, &str); 4] = [
+    ("abs", "length({})"),
+    ("re", "{}.x"),
+    ("im", "{}.y"),
+    ("arg", "atan({0}.y, {0}.x)"),
+];
+
+fn float_literal(value: f64) -> String {
+    // Debug formatting always includes a decimal point or exponent, so GLSL reads it as a float
+    format!("{value:?}")
+}
+
+fn variable(name: &str) -> Result<Typed, String> {
+    Ok(match name {
+        "z" => Typed::complex(String::from("z")),
+        "c" => Typed::complex(String::from("c")),
+        "i" => Typed::complex(String::from("vec2(0.0, 1.0)")),
+        "pi" => Typed::real(float_literal(std::f64::consts::PI)),
+        "e" => Typed::real(float_literal(std::f64::consts::E)),
+        _ => {
+            return Err(format!(
+                "Unknown variable '{name}', expected z, c, i, pi or e"
+            ))
+        }
+    })
+}
+
+fn call(name: &str, arguments: &[Expression]) -> Result<Typed, String> {
+    let [argument] = arguments else {
+        return Err(format!(
+            "{name}() takes 1 argument but was given {}",
+            arguments.len()
+        ));
+    };
+    let argument = generate(argument)?;
+    if let Some((_, helper)) = COMPLEX_FUNCTIONS
+        .iter()
+        .find(|(function, _)| *function == name)
+    {
+        return Ok(Typed::complex(format!(
+            "{helper}({})",
+            argument.as_complex()
+        )));
+    }
+    if let Some((_, template)) = REAL_FUNCTIONS
+        .iter()
+        .find(|(function, _)| *function == name)
+    {
+        let argument = format!("({})", argument.as_complex());
+        return Ok(Typed::real(
+            template.replace("{}", &argument).replace("{0}", &argument),
+        ));
+    }
+    Err(format!("Unknown function '{name}'"))
+}
+
+fn power(base: Typed, exponent: &Expression) -> Result<Typed, String> {
+    if let Expression::Number(value) = exponent {
+        if value.fract() == 0.0 && (0.0..=MAX_INTEGER_POWER).contains(value) {
+            let exponent = *value as i32;
+            return Ok(match base.ty {
+                Type::Real => Typed::real(format!("rpowi({}, {exponent})", base.source)),
+                Type::Complex => Typed::complex(format!("cpowi({}, {exponent})", base.source)),
+            });
+        }
+    }
+    let exponent = generate(exponent)?;
+    Ok(Typed::complex(format!(
+        "cpow({}, {})",
+        base.as_complex(),
+        exponent.as_complex()
+    )))
+}
+
+fn binary(operator: Operator, left: &Expression, right: &Expression) -> Result<Typed, String> {
+    let left = generate(left)?;
+    if operator == Operator::Power {
+        return power(left, right);
+    }
+    let right = generate(right)?;
+    let both_real = left.ty == Type::Real && right.ty == Type::Real;
+    Ok(match operator {
+        Operator::Add | Operator::Subtract => {
+            let symbol = if operator == Operator::Add { '+' } else { '-' };
+            if both_real {
+                Typed::real(format!("({} {symbol} {})", left.source, right.source))
+            } else {
+                Typed::complex(format!(
+                    "({} {symbol} {})",
+                    left.as_complex(),
+                    right.as_complex()
+                ))
+            }
+        }
+        // Scaling a vec2 by a float already multiplies complex numbers by real ones
+        Operator::Multiply if left.ty == Type::Real || right.ty == Type::Real => Typed {
+            source: format!("({} * {})", left.source, right.source),
+            ty: if both_real { Type::Real } else { Type::Complex },
+        },
+        Operator::Multiply => Typed::complex(format!("cmul({}, {})", left.source, right.source)),
+        Operator::Divide if right.ty == Type::Real => Typed {
+            source: format!("({} / {})", left.source, right.source),
+            ty: left.ty,
+        },
+        Operator::Divide => {
+            Typed::complex(format!("cdiv({}, {})", left.as_complex(), right.source))
+        }
+        Operator::Power => unreachable!(),
+    })
+}
+
+fn generate(expression: &Expression) -> Result<Typed, String> {
+    match expression {
+        Expression::Number(value) => Ok(Typed::real(float_literal(*value))),
+        Expression::Variable(name) => variable(name),
+        Expression::Negate(inner) => {
+            let inner = generate(inner)?;
+            Ok(Typed {
+                source: format!("(-{})", inner.source),
+                ty: inner.ty,
+            })
+        }
+        Expression::Binary(operator, left, right) => binary(*operator, left, right),
+        Expression::Call(name, arguments) => call(name, arguments),
+    }
+}
+
+/// Type-checks a formula and turns it into a GLSL `vec2` expression of `z` and `c`, using the helpers in `complex.glsl`
+pub fn to_glsl(expression: &Expression) -> Result<String, String> {
+    Ok(generate(expression)?.as_complex())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::{parse, to_glsl};
+
+    fn glsl(source: &str) -> Result<String, String> {
+        to_glsl(&parse(source)?)
+    }
+
+    #[test]
+    fn generates_glsl() {
+        assert_eq!(
+            glsl("z^3 + c*sin(z)"),
+            Ok(String::from("(cpowi(z, 3) + cmul(c, csin(z)))"))
+        );
+        assert_eq!(
+            glsl("2z - 1"),
+            Ok(String::from("((2.0 * z) - vec2(1.0, 0.0))"))
+        );
+        assert_eq!(
+            glsl("z^1.5 / abs(z)"),
+            Ok(String::from("(cpow(z, vec2(1.5, 0.0)) / length((z)))"))
+        );
+        assert_eq!(glsl("1 / z"), Ok(String::from("cdiv(vec2(1.0, 0.0), z)")));
+        assert_eq!(
+            glsl("arg(z)"),
+            Ok(String::from("vec2(atan((z).y, (z).x), 0.0)"))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert_eq!(
+            glsl("x^2 + c"),
+            Err(String::from(
+                "Unknown variable 'x', expected z, c, i, pi or e"
+            ))
+        );
+        assert_eq!(
+            glsl("gamma(z)"),
+            Err(String::from("Unknown function 'gamma'"))
+        );
+        assert_eq!(
+            glsl("sin(z, c)"),
+            Err(String::from("sin() takes 1 argument but was given 2"))
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Complex expressions typed in by the user, such as `z^3 + c*sin(z)`
+
+pub use glsl::to_glsl;
+
+mod glsl;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(String, Vec<Expression>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Identifier(String),
+    Symbol(char),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    /// Column of the first character, counting from 1
+    column: usize,
+}
+
+fn tokenise(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let (char, column) = (chars[index], index + 1);
+        let kind = if char.is_whitespace() {
+            index += 1;
+            continue;
+        } else if char.is_ascii_digit() || char == '.' {
+            let start = index;
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') {
+                index += 1;
+            }
+            // Only treat an e as an exponent if digits follow it, so 2e is still 2 * e
+            if index < chars.len() && matches!(chars[index], 'e' | 'E') {
+                let digits = match chars.get(index + 1) {
+                    Some('+' | '-') => index + 2,
+                    _ => index + 1,
+                };
+                if chars.get(digits).is_some_and(char::is_ascii_digit) {
+                    index = digits;
+                    while index < chars.len() && chars[index].is_ascii_digit() {
+                        index += 1;
+                    }
+                }
+            }
+            let number: String = chars[start..index].iter().collect();
+            TokenKind::Number(
+                number
+                    .parse()
+                    .map_err(|_| format!("Invalid number '{number}' at column {column}"))?,
+            )
+        } else if char.is_alphabetic() || char == '_' {
+            let start = index;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            TokenKind::Identifier(chars[start..index].iter().collect())
+        } else if "+-*/^(),".contains(char) {
+            index += 1;
+            TokenKind::Symbol(char)
+        } else {
+            return Err(format!("Unexpected '{char}' at column {column}"));
+        };
+        tokens.push(Token { kind, column });
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser, from the lowest precedence to the highest
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Column just past the end of the source, for errors at the end
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |token| token.column)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&TokenKind::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{symbol}' at column {}", self.column()))
+        }
+    }
+
+    /// `a + b` and `a - b`
+    fn sum(&mut self) -> Result<Expression, String> {
+        let mut left = self.product()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(left);
+            };
+            left = Expression::Binary(operator, Box::new(left), Box::new(self.product()?));
+        }
+    }
+
+    /// `a * b`, `a / b` and implicit multiplication like `2z`
+    fn product(&mut self) -> Result<Expression, String> {
+        let mut left = self.unary()?;
+        loop {
+            let operator = if self.eat('*') {
+                Operator::Multiply
+            } else if self.eat('/') {
+                Operator::Divide
+            } else if matches!(
+                self.peek(),
+                Some(TokenKind::Number(_) | TokenKind::Identifier(_) | TokenKind::Symbol('('))
+            ) {
+                Operator::Multiply
+            } else {
+                return Ok(left);
+            };
+            left = Expression::Binary(operator, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    /// `-a`, which binds looser than powers so `-z^2` is `-(z^2)`
+    fn unary(&mut self) -> Result<Expression, String> {
+        if self.eat('-') {
+            Ok(Expression::Negate(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    /// `a ^ b`, which is right associative
+    fn power(&mut self) -> Result<Expression, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            Ok(Expression::Binary(
+                Operator::Power,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expression, String> {
+        let column = self.column();
+        let Some(kind) = self.peek().cloned() else {
+            return Err(format!("Unexpected end of formula at column {column}"));
+        };
+        self.position += 1;
+        match kind {
+            TokenKind::Number(value) => Ok(Expression::Number(value)),
+            TokenKind::Identifier(name) => {
+                if !self.eat('(') {
+                    return Ok(Expression::Variable(name));
+                }
+                let mut arguments = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        arguments.push(self.sum()?);
+                        if !self.eat(',') {
+                            self.expect(')')?;
+                            break;
+                        }
+                    }
+                }
+                Ok(Expression::Call(name, arguments))
+            }
+            TokenKind::Symbol('(') => {
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            TokenKind::Symbol(symbol) => Err(format!("Unexpected '{symbol}' at column {column}")),
+        }
+    }
+}
+
+/// Parses a formula, returning a message pointing at the problem if it's malformed
+pub fn parse(source: &str) -> Result<Expression, String> {
+    let mut parser = Parser {
+        tokens: tokenise(source)?,
+        position: 0,
+        end: source.chars().count() + 1,
+    };
+    let expression = parser.sum()?;
+    if parser.position < parser.tokens.len() {
+        return Err(format!("Unexpected input at column {}", parser.column()));
+    }
+    Ok(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::{parse, Expression, Operator};
+
+    fn binary(operator: Operator, left: Expression, right: Expression) -> Expression {
+        Expression::Binary(operator, Box::new(left), Box::new(right))
+    }
+
+    fn variable(name: &str) -> Expression {
+        Expression::Variable(String::from(name))
+    }
+
+    #[test]
+    fn precedence() {
+        // z^3 + c*sin(z)
+        assert_eq!(
+            parse("z^3 + c*sin(z)"),
+            Ok(binary(
+                Operator::Add,
+                binary(Operator::Power, variable("z"), Expression::Number(3.0)),
+                binary(
+                    Operator::Multiply,
+                    variable("c"),
+                    Expression::Call(String::from("sin"), vec![variable("z")])
+                ),
+            ))
+        );
+        // -z^2^0.5 is -(z^(2^0.5)) and 2z is 2 * z
+        assert_eq!(
+            parse("-z^2^0.5"),
+            Ok(Expression::Negate(Box::new(binary(
+                Operator::Power,
+                variable("z"),
+                binary(
+                    Operator::Power,
+                    Expression::Number(2.0),
+                    Expression::Number(0.5)
+                ),
+            ))))
+        );
+        assert_eq!(
+            parse("2z"),
+            Ok(binary(
+                Operator::Multiply,
+                Expression::Number(2.0),
+                variable("z")
+            ))
+        );
+        assert_eq!(parse("1e-3"), Ok(Expression::Number(1e-3)));
+        assert_eq!(
+            parse("2e"),
+            Ok(binary(
+                Operator::Multiply,
+                Expression::Number(2.0),
+                variable("e")
+            ))
+        );
+    }
+
+    #[test]
+    fn errors_point_at_problem() {
+        assert_eq!(
+            parse("z^2 +"),
+            Err(String::from("Unexpected end of formula at column 6"))
+        );
+        assert_eq!(
+            parse("sin(z"),
+            Err(String::from("Expected ')' at column 6"))
+        );
+        assert_eq!(
+            parse("z ? c"),
+            Err(String::from("Unexpected '?' at column 3"))
+        );
+        assert_eq!(
+            parse("(z))"),
+            Err(String::from("Unexpected input at column 4"))
+        );
+    }
+}
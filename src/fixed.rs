@@ -0,0 +1,282 @@
+use std::{
+    cmp::Ordering,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+/// Signed fixed-point number with a 32 bit integer part and an arbitrary number of 32 bit fractional limbs
+///
+/// Only intended for values of a similar magnitude to the Mandelbrot set, overflow of the integer part is discarded
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fixed {
+    negative: bool,
+    /// Big-endian base 2^32 digits, `limbs[0]` is the integer part
+    limbs: Vec<u32>,
+}
+
+/// Most fractional bits any [`f64`] scale needs, as the smallest positive one is 2^-1074
+const MAX_SCALE_BITS: f64 = 1075.0;
+
+/// Number of fractional limbs needed to address points `scale` apart, with some headroom for the iteration
+///
+/// A scale of 0 gets as many as the smallest [`f64`], and a non-finite one as few as a scale of 1
+pub fn precision_for_scale(scale: f64) -> usize {
+    let bits = (-scale.abs().log2()).clamp(0.0, MAX_SCALE_BITS) as usize + 64;
+    bits.div_ceil(32)
+}
+
+impl Fixed {
+    pub fn zero(precision: usize) -> Self {
+        Self {
+            negative: false,
+            limbs: vec![0; precision + 1],
+        }
+    }
+
+    /// Converts an [`f64`], truncating any bits below the requested precision
+    pub fn from_f64(value: f64, precision: usize) -> Self {
+        let mut result = Self::zero(precision);
+        let mut remaining = value.abs();
+        for limb in result.limbs.iter_mut() {
+            let digit = remaining.floor();
+            *limb = digit as u32;
+            remaining = (remaining - digit) * 4294967296.0;
+        }
+        result.negative = value < 0.0;
+        result.normalise()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let mut result = 0.0;
+        for limb in self.limbs.iter().rev() {
+            result = result / 4294967296.0 + *limb as f64;
+        }
+        if self.negative {
+            -result
+        } else {
+            result
+        }
+    }
+
+    /// Number of fractional limbs
+    pub fn precision(&self) -> usize {
+        self.limbs.len() - 1
+    }
+
+    /// Truncates or zero-extends to the given number of fractional limbs
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.limbs.resize(precision + 1, 0);
+        self.normalise()
+    }
+
+    /// Exact text form like `-1.80000000` for -1.5, with the integer part and then each fractional limb as 8 digits
+    /// in hexadecimal, leaving off trailing zero limbs
+    pub fn to_hex(&self) -> String {
+        let fraction_limbs = self.limbs.iter().rposition(|limb| *limb != 0).unwrap_or(0);
+        let mut hex = format!(
+            "{}{:x}",
+            if self.negative { "-" } else { "" },
+            self.limbs[0]
+        );
+        if fraction_limbs > 0 {
+            hex.push('.');
+            for limb in &self.limbs[1..=fraction_limbs] {
+                hex += &format!("{limb:08x}");
+            }
+        }
+        hex
+    }
+
+    /// Parses the output of [`Fixed::to_hex`], with at least `precision` fractional limbs
+    pub fn from_hex(hex: &str, precision: usize) -> Option<Self> {
+        let (negative, hex) = match hex.strip_prefix('-') {
+            Some(hex) => (true, hex),
+            None => (false, hex),
+        };
+        let (integer, fraction) = hex.split_once('.').unwrap_or((hex, ""));
+        if fraction.len() % 8 != 0 || !fraction.is_ascii() {
+            return None;
+        }
+        let mut limbs = vec![u32::from_str_radix(integer, 16).ok()?];
+        for start in (0..fraction.len()).step_by(8) {
+            limbs.push(u32::from_str_radix(&fraction[start..start + 8], 16).ok()?);
+        }
+        let precision = precision.max(limbs.len() - 1);
+        Some(Self { negative, limbs }.with_precision(precision))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|limb| *limb == 0)
+    }
+
+    fn normalise(mut self) -> Self {
+        if self.is_zero() {
+            self.negative = false;
+        }
+        self
+    }
+
+    fn padded(&self, precision: usize) -> Vec<u32> {
+        let mut limbs = self.limbs.clone();
+        limbs.resize(precision + 1, 0);
+        limbs
+    }
+
+    fn add_signed(&self, rhs: &Fixed, rhs_negative: bool) -> Fixed {
+        let precision = self.precision().max(rhs.precision());
+        let a = self.padded(precision);
+        let b = rhs.padded(precision);
+        let (negative, limbs) = if self.negative == rhs_negative {
+            (self.negative, add_magnitudes(&a, &b))
+        } else {
+            match compare_magnitudes(&a, &b) {
+                Ordering::Less => (rhs_negative, sub_magnitudes(&b, &a)),
+                _ => (self.negative, sub_magnitudes(&a, &b)),
+            }
+        };
+        Fixed { negative, limbs }.normalise()
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.iter().cmp(b.iter())
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0; a.len()];
+    let mut carry = 0_u64;
+    for i in (0..a.len()).rev() {
+        let sum = a[i] as u64 + b[i] as u64 + carry;
+        result[i] = sum as u32;
+        carry = sum >> 32;
+    }
+    result
+}
+
+/// Subtracts `b` from `a`, assuming `a >= b`
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0; a.len()];
+    let mut borrow = 0_i64;
+    for i in (0..a.len()).rev() {
+        let mut difference = a[i] as i64 - b[i] as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        result[i] = difference as u32;
+    }
+    result
+}
+
+fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let len = a.len();
+    // `product[k]` has weight 2^(32 * (1 - k)), so the integer limb lands in `product[1]`
+    let mut product = vec![0_u32; len * 2];
+    for i in (0..len).rev() {
+        let mut carry = 0_u64;
+        for j in (0..len).rev() {
+            let t = a[i] as u64 * b[j] as u64 + product[i + j + 1] as u64 + carry;
+            product[i + j + 1] = t as u32;
+            carry = t >> 32;
+        }
+        product[i] = carry as u32;
+    }
+    product[1..=len].to_vec()
+}
+
+impl Add for &Fixed {
+    type Output = Fixed;
+    fn add(self, rhs: Self) -> Self::Output {
+        self.add_signed(rhs, rhs.negative)
+    }
+}
+
+impl Sub for &Fixed {
+    type Output = Fixed;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.add_signed(rhs, !rhs.negative)
+    }
+}
+
+impl Mul for &Fixed {
+    type Output = Fixed;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let precision = self.precision().max(rhs.precision());
+        Fixed {
+            negative: self.negative != rhs.negative,
+            limbs: mul_magnitudes(&self.padded(precision), &rhs.padded(precision)),
+        }
+        .normalise()
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(mut self) -> Self::Output {
+        self.negative = !self.negative;
+        self.normalise()
+    }
+}
+
+impl Default for Fixed {
+    fn default() -> Self {
+        Self::zero(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixed::{precision_for_scale, Fixed};
+
+    #[test]
+    fn f64_round_trip() {
+        for value in [0.0, 1.5, -0.75, 1.0e-30, -1.234_567_890_123_4, 3.0e9] {
+            assert_eq!(Fixed::from_f64(value, 6).to_f64(), value);
+        }
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let value = &Fixed::from_f64(-1.8, 3) - &Fixed::from_f64(2_f64.powi(-90), 3);
+        assert_eq!(value.to_hex(), "-1.ccccccccccccd00000000040");
+        assert_eq!(Fixed::from_hex(&value.to_hex(), 3), Some(value));
+        assert_eq!(Fixed::zero(2).to_hex(), "0");
+        assert_eq!(Fixed::from_hex("0.8", 1), None);
+        assert_eq!(Fixed::from_hex("2.8000000g", 1), None);
+        assert_eq!(Fixed::from_hex("2", 1), Some(Fixed::from_f64(2.0, 1)));
+    }
+
+    #[test]
+    fn add_sub() {
+        let a = Fixed::from_f64(1.25, 2);
+        let b = Fixed::from_f64(-2.5, 2);
+        assert_eq!((&a + &b).to_f64(), -1.25);
+        assert_eq!((&a - &b).to_f64(), 3.75);
+        assert_eq!((&b - &b), Fixed::zero(2));
+    }
+
+    #[test]
+    fn mul() {
+        let a = Fixed::from_f64(-1.5, 3);
+        let b = Fixed::from_f64(0.375, 3);
+        assert_eq!((&a * &b).to_f64(), -0.5625);
+        assert_eq!((&a * &a).to_f64(), 2.25);
+    }
+
+    #[test]
+    fn precision_beyond_f64() {
+        // 1 + 2^-100 can't be represented as an f64, but its difference from 1 can
+        let tiny = Fixed::from_f64(2_f64.powi(-100), 4);
+        let one = Fixed::from_f64(1.0, 4);
+        assert_eq!((&(&one + &tiny) - &one).to_f64(), 2_f64.powi(-100));
+    }
+
+    #[test]
+    fn precision_for_any_scale() {
+        assert_eq!(precision_for_scale(1.0), 2);
+        assert_eq!(precision_for_scale(2_f64.powi(-100)), 6);
+        assert_eq!(precision_for_scale(0.0), 36);
+        assert_eq!(precision_for_scale(f64::INFINITY), 2);
+        assert_eq!(precision_for_scale(f64::NAN), 2);
+    }
+}
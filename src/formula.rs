@@ -0,0 +1,166 @@
+use crate::{
+    fixed::{precision_for_scale, Fixed},
+    lyapunov,
+    multibrot::{default_view, escape_radius},
+    view::View,
+};
+
+/// Radius beyond which z escapes for custom formulas, which is large enough for most that grow like a power of z
+const CUSTOM_BAILOUT: f64 = 16.0;
+
+/// Escape-time formula drawn by `shader.frag`, each a variation on `z^power + c`, or one of the modes with their own
+/// program
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Formula {
+    #[default]
+    Mandelbrot,
+    /// Takes the absolute value of both components before raising z
+    BurningShip,
+    /// Conjugates z before raising it, also known as the Mandelbar set
+    Tricorn,
+    /// Takes the absolute value of the real part after raising z
+    Celtic,
+    /// Takes the absolute value of both components after raising z
+    Buffalo,
+    /// Formula typed in by the user, see `compile_custom` in `shader/mod.rs`
+    Custom,
+    /// Runs of the other formulas in turn, see [`crate::hybrid::Hybrid`]
+    Hybrid,
+    /// Newton's method for a polynomial typed in by the user, drawn by `newton.frag`
+    Newton,
+    /// Lyapunov exponents of the logistic map over the (a, b) plane, drawn by `lyapunov.frag`
+    Lyapunov,
+}
+
+impl Formula {
+    pub const ALL: [Formula; 5] = [
+        Formula::Mandelbrot,
+        Formula::BurningShip,
+        Formula::Tricorn,
+        Formula::Celtic,
+        Formula::Buffalo,
+    ];
+
+    /// Value of the `formula` uniform in `shader.frag`
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Formula::Mandelbrot => "Mandelbrot",
+            Formula::BurningShip => "Burning Ship",
+            Formula::Tricorn => "Tricorn",
+            Formula::Celtic => "Celtic",
+            Formula::Buffalo => "Buffalo",
+            Formula::Custom => "Custom",
+            Formula::Hybrid => "Hybrid",
+            Formula::Newton => "Newton",
+            Formula::Lyapunov => "Lyapunov",
+        }
+    }
+
+    /// Parses a name like `"burning_ship"` or `"Burning Ship"`, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        let simplify = |name: &str| name.to_lowercase().replace([' ', '_', '-'], "");
+        Self::ALL
+            .into_iter()
+            .find(|formula| simplify(formula.name()) == simplify(name))
+    }
+
+    /// Formula after this one in [`Formula::ALL`], wrapping around, or before it when `step` is negative
+    ///
+    /// Modes which aren't in [`Formula::ALL`] count from [`Formula::Mandelbrot`]
+    pub fn cycle(self, step: isize) -> Self {
+        let position = Self::ALL
+            .iter()
+            .position(|formula| *formula == self)
+            .unwrap_or(0);
+        let index = position as isize + step;
+        Self::ALL[index.rem_euclid(Self::ALL.len() as isize) as usize]
+    }
+
+    /// Radius beyond which z escapes
+    ///
+    /// Every built-in formula keeps `|z|` the same as raising it does, so they all share the Multibrot radius
+    pub fn bailout(self, power: f64) -> f64 {
+        match self {
+            Formula::Custom => CUSTOM_BAILOUT,
+            _ => escape_radius(power),
+        }
+    }
+
+    /// View which fits the set for `power`
+    ///
+    /// Other powers reshape the variations too much to frame by hand, so they're centred like the Multibrot sets
+    pub fn default_view(self, power: f64) -> View {
+        match self {
+            Formula::Custom => return default_view(2.0),
+            Formula::Hybrid => return default_view(power),
+            // Fits the roots of unity, `newton::view_for_roots` frames any other polynomial
+            Formula::Newton => {
+                return View {
+                    scale: 2.0,
+                    ..Default::default()
+                }
+            }
+            Formula::Lyapunov => return lyapunov::default_view(),
+            _ => {}
+        }
+        if power != 2.0 {
+            return default_view(power);
+        }
+        let (center, scale) = match self {
+            Formula::Mandelbrot => return default_view(power),
+            Formula::BurningShip => ((-0.4, -0.5), 1.9),
+            Formula::Tricorn => ((-0.3, 0.0), 2.2),
+            Formula::Celtic => ((-0.6, 0.0), 2.0),
+            Formula::Buffalo => ((-0.5, -0.6), 2.0),
+            Formula::Custom | Formula::Hybrid | Formula::Newton | Formula::Lyapunov => {
+                unreachable!()
+            }
+        };
+        let precision = precision_for_scale(scale);
+        View {
+            center: (
+                Fixed::from_f64(center.0, precision),
+                Fixed::from_f64(center.1, precision),
+            ),
+            scale,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formula::Formula;
+
+    #[test]
+    fn names_round_trip() {
+        for formula in Formula::ALL {
+            assert_eq!(Formula::from_name(formula.name()), Some(formula));
+        }
+        assert_eq!(
+            Formula::from_name("burning_ship"),
+            Some(Formula::BurningShip)
+        );
+        assert_eq!(Formula::from_name("mandelbulb"), None);
+    }
+
+    #[test]
+    fn cycle_wraps() {
+        assert_eq!(Formula::Buffalo.cycle(1), Formula::Mandelbrot);
+        assert_eq!(Formula::Mandelbrot.cycle(-1), Formula::Buffalo);
+        assert_eq!(Formula::Tricorn.cycle(1), Formula::Celtic);
+        for formula in [
+            Formula::Custom,
+            Formula::Hybrid,
+            Formula::Newton,
+            Formula::Lyapunov,
+        ] {
+            assert_eq!(formula.cycle(1), Formula::BurningShip);
+            assert_eq!(formula.cycle(-1), Formula::Buffalo);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+
+use crate::formula::Formula;
+
+/// Most iterations one cycle of a hybrid can take, as each is unrolled into the shader
+pub const MAX_HYBRID_ITERATIONS: u32 = 64;
+
+/// Run of iterations of one formula in a [`Hybrid`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HybridStep {
+    pub formula: Formula,
+    pub count: u32,
+    /// Whether to take the absolute value of the real and imaginary parts of z before each iteration
+    pub abs_re: bool,
+    pub abs_im: bool,
+}
+
+/// Formula which cycles through runs of the built-in formulas, like the hybrids in Kalles Fraktaler
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hybrid {
+    pub steps: Vec<HybridStep>,
+}
+
+impl HybridStep {
+    /// Parses a step like `2x Burning Ship abs(im)`, where the count defaults to 1
+    fn parse(source: &str) -> Result<Self, String> {
+        let source = source.trim().to_lowercase();
+        let digits = source.len()
+            - source
+                .trim_start_matches(|char: char| char.is_ascii_digit())
+                .len();
+        let (count, mut rest) = match digits {
+            0 => (1, source.as_str()),
+            _ => {
+                let rest = source[digits..].trim_start();
+                (
+                    source[..digits]
+                        .parse()
+                        .map_err(|_| format!("'{}' is too many iterations", &source[..digits]))?,
+                    rest.strip_prefix(['x', '*']).unwrap_or(rest),
+                )
+            }
+        };
+        let (mut abs_re, mut abs_im) = (false, false);
+        loop {
+            rest = rest.trim();
+            if let Some(remaining) = rest.strip_suffix("abs(re)") {
+                abs_re = true;
+                rest = remaining;
+            } else if let Some(remaining) = rest.strip_suffix("abs(im)") {
+                abs_im = true;
+                rest = remaining;
+            } else {
+                break;
+            }
+        }
+        let formula = Formula::from_name(rest).ok_or_else(|| {
+            format!("Unknown formula '{rest}', hybrids can use Mandelbrot, Burning Ship, Tricorn, Celtic or Buffalo")
+        })?;
+        if count == 0 {
+            return Err(String::from("Steps need at least one iteration"));
+        }
+        Ok(Self {
+            formula,
+            count,
+            abs_re,
+            abs_im,
+        })
+    }
+}
+
+impl Hybrid {
+    /// Parses a comma separated list of steps like `2x Mandelbrot, 1x Burning Ship abs(im)`, ignoring case
+    ///
+    /// Each step is an optional count, the name of one of [`Formula::ALL`] and optionally `abs(re)` or `abs(im)`
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let steps = source
+            .split(',')
+            .map(HybridStep::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        let total = steps
+            .iter()
+            .fold(0_u32, |total, step| total.saturating_add(step.count));
+        if total > MAX_HYBRID_ITERATIONS {
+            return Err(format!(
+                "Hybrids can take at most {MAX_HYBRID_ITERATIONS} iterations before repeating"
+            ));
+        }
+        Ok(Self { steps })
+    }
+
+    /// Body of `shader.frag`'s `formula.glsl`, defining `hybrid(z, dz, offset, dc)` which iterates like `pixel` with
+    /// every iteration of a cycle unrolled
+    pub fn to_glsl(&self) -> String {
+        let mut glsl = String::from(
+            "#define HYBRID_FORMULA\n\
+             vec2 hybrid(vec2 z, vec2 dz, vec2 offset, vec2 dc) {\n    \
+             for (int i = 0; i < depth;) {\n",
+        );
+        for step in &self.steps {
+            let re = if step.abs_re { "abs(z.x)" } else { "z.x" };
+            let im = if step.abs_im { "abs(z.y)" } else { "z.y" };
+            // abs() reflects the derivative along with z
+            let d_re = if step.abs_re {
+                "(z.x < 0.0 ? -dz.x : dz.x)"
+            } else {
+                "dz.x"
+            };
+            let d_im = if step.abs_im {
+                "(z.y < 0.0 ? -dz.y : dz.y)"
+            } else {
+                "dz.y"
+            };
+            for _ in 0..step.count {
+                glsl += &format!(
+                    "        if (distance_estimation) dz = derivative(vec2({re}, {im}), vec2({d_re}, {d_im}), {id}) + dc;\n        \
+                     z = iterate(vec2({re}, {im}), {id}) + offset;\n        \
+                     if (length(z) > escape_radius) {{\n            \
+                     return vec2(escape_ratio(i, z, escape_radius, power), distance_estimate(z, dz));\n        \
+                     }}\n        \
+                     if (++i == depth) break;\n",
+                    id = step.formula.id()
+                );
+            }
+        }
+        glsl + "    }\n    return vec2(1.0, -1.0);\n}\n"
+    }
+}
+
+impl fmt::Display for Hybrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}x {}", step.count, step.formula.name())?;
+            if step.abs_re {
+                write!(f, " abs(re)")?;
+            }
+            if step.abs_im {
+                write!(f, " abs(im)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        formula::Formula,
+        hybrid::{Hybrid, HybridStep},
+    };
+
+    #[test]
+    fn parses_steps() {
+        let hybrid = Hybrid::parse("2x Mandelbrot, burning_ship ABS(im)").unwrap();
+        assert_eq!(
+            hybrid.steps,
+            vec![
+                HybridStep {
+                    formula: Formula::Mandelbrot,
+                    count: 2,
+                    abs_re: false,
+                    abs_im: false,
+                },
+                HybridStep {
+                    formula: Formula::BurningShip,
+                    count: 1,
+                    abs_re: false,
+                    abs_im: true,
+                },
+            ]
+        );
+        assert_eq!(hybrid.to_string(), "2x Mandelbrot, 1x Burning Ship abs(im)");
+        assert_eq!(Hybrid::parse(&hybrid.to_string()), Ok(hybrid));
+        assert!(Hybrid::parse("2x Mandelbulb").is_err());
+        assert!(Hybrid::parse("0x Tricorn").is_err());
+        assert!(Hybrid::parse("Tricorn,").is_err());
+        assert!(Hybrid::parse("40x Celtic, 30x Buffalo").is_err());
+        assert!(Hybrid::parse("4294967295x Mandelbrot, 1x Mandelbrot").is_err());
+    }
+
+    #[test]
+    fn unrolls_every_iteration() {
+        let glsl = Hybrid::parse("2 Mandelbrot, Buffalo abs(re) abs(im)")
+            .unwrap()
+            .to_glsl();
+        assert_eq!(glsl.matches("z = iterate(vec2(z.x, z.y), 0)").count(), 2);
+        assert_eq!(
+            glsl.matches("z = iterate(vec2(abs(z.x), abs(z.y)), 4)")
+                .count(),
+            1
+        );
+        assert_eq!(glsl.matches("if (++i == depth) break;").count(), 3);
+        // abs() reflects the derivative too
+        assert_eq!(
+            glsl.matches("vec2((z.x < 0.0 ? -dz.x : dz.x), (z.y < 0.0 ? -dz.y : dz.y)), 4)")
+                .count(),
+            1
+        );
+    }
+}
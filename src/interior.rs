@@ -0,0 +1,70 @@
+/// How points inside an escape-time set are coloured
+///
+/// Apart from [`Interior::Flat`], `shader.frag` keeps iterating points which didn't escape until their orbit comes
+/// back round to itself, finding the attracting cycle it settled into. The other precisions and custom formulas and
+/// hybrids are always flat
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Interior {
+    /// The colour at the end of the palette, like a point which escaped on the last iteration
+    #[default]
+    Flat,
+    /// Period of the cycle, which is the same across each hyperbolic component
+    Period,
+    /// Angle and magnitude of the cycle's multiplier, its derivative once around the cycle, which goes from 0 at the
+    /// centre of each component to 1 at its edge
+    Multiplier,
+    /// Estimated distance to the edge of the set, only for the Mandelbrot set with a power of 2
+    Distance,
+}
+
+impl Interior {
+    pub const ALL: [Interior; 4] = [
+        Interior::Flat,
+        Interior::Period,
+        Interior::Multiplier,
+        Interior::Distance,
+    ];
+
+    /// Value of the `interior` uniform in `shader.frag` and `colour.frag`
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Interior::Flat => "Flat",
+            Interior::Period => "Period",
+            Interior::Multiplier => "Multiplier",
+            Interior::Distance => "Distance",
+        }
+    }
+
+    /// Mode called `name`, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|interior| interior.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Mode after this one in [`Interior::ALL`], wrapping around, or before it when `step` is negative
+    pub fn cycle(self, step: isize) -> Self {
+        let index = self.id() as isize + step;
+        Self::ALL[index.rem_euclid(Self::ALL.len() as isize) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interior::Interior;
+
+    #[test]
+    fn names_and_cycle() {
+        for interior in Interior::ALL {
+            assert_eq!(Interior::from_name(interior.name()), Some(interior));
+        }
+        assert_eq!(Interior::from_name(" period"), Some(Interior::Period));
+        assert_eq!(Interior::from_name("periodic"), None);
+        assert_eq!(Interior::Distance.cycle(1), Interior::Flat);
+        assert_eq!(Interior::Flat.cycle(-1), Interior::Distance);
+    }
+}
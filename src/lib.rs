@@ -1,36 +1,185 @@
 extern crate console_error_panic_hook;
 use std::{cell::RefCell, panic, rc::Rc, sync::LazyLock};
 
+use cpu::{Buddhabrot, BUDDHABROT_DEPTHS, NEBULABROT_DEPTHS};
+use depth::{Depth, MAX_DEPTH, MIN_DEPTH};
+use expression::{parse, to_glsl};
+use fixed::Fixed;
+use formula::Formula;
 use fragile::Fragile;
-use shader::{draw, init_shaders, CanvasState};
-use util::{console_log, request_animation_frame, start_animation_loop};
-use viewport::ViewportController;
+use hybrid::Hybrid;
+use interior::Interior;
+use lyapunov::parse_sequence;
+use matrix::Matrix3;
+use multibrot::{MAX_POWER, MIN_POWER, SMOOTH_BAILOUT};
+use newton::{view_for_roots, Polynomial};
+use palette::{builtin, Format, Mapping, Palette, BUILTIN_PALETTES};
+use params::Params;
+use perturbation::{find_glitch, ReferenceOrbit};
+use raymarch::Shape;
+use shader::{
+    compile_custom, compile_hybrid, draw, draw_double, draw_perturbation, draw_raymarch,
+    draw_tiles, init_shaders, pass_progress, present, present_image, read_iterations, region_size,
+    resolution, set_depth, set_formula, set_interior, set_julia, set_line_thickness, set_palette,
+    set_polynomial, set_region, set_resolution, set_sequence, set_smooth, set_tile_size,
+    upload_orbit, CanvasState, Precision,
+};
+use tiles::TileBudget;
+use util::{
+    add_event_listener, console_log, request_animation_frame, set_timeout, start_animation_loop,
+};
+use viewport::{Mode, Pane, ViewportController};
 use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
 use web_sys::{
-    window, HtmlCanvasElement, HtmlElement, Performance, WebGl2RenderingContext, Window,
+    window, Event, HtmlCanvasElement, HtmlElement, HtmlInputElement, Performance,
+    WebGl2RenderingContext, Window,
 };
 
-mod matrix;
+mod complex;
+pub mod cpu;
+mod depth;
+mod expression;
+pub mod fixed;
+mod formula;
+mod hybrid;
+mod interior;
+mod lyapunov;
+pub mod matrix;
+mod multibrot;
+mod newton;
+pub mod palette;
+mod params;
+mod perturbation;
+mod raymarch;
 mod shader;
+mod tiles;
 mod util;
+pub mod view;
 mod viewport;
 
-const DEPTH: u16 = 1000;
+/// Most extra references to place in glitched areas per frame
+const MAX_EXTRA_REFERENCES: usize = 8;
+
+/// Width in pixels of the lines from distance estimation, see [`App::multiply_line_thickness`]
+const DEFAULT_LINE_THICKNESS: f64 = 1.0;
+const MIN_LINE_THICKNESS: f64 = 0.25;
+const MAX_LINE_THICKNESS: f64 = 16.0;
+
+/// `c` of the Julia set shown before one is picked
+const DEFAULT_JULIA_C: (f64, f64) = (-0.8, 0.156);
+
+/// How long the view has to stay still before a preview is redrawn at full resolution
+const IDLE_MS: f64 = 150.0;
+
+/// Time spent tracing Buddhabrot orbits on the CPU each frame
+const BUDDHABROT_FRAME_MS: f64 = 15.0;
+
+/// Orbits traced per batch while filling a frame's time with Buddhabrot samples
+const BUDDHABROT_BATCH: usize = 2000;
+
+/// Samples per pixel after which a Buddhabrot is left as it is
+const BUDDHABROT_SAMPLES_PER_PIXEL: u64 = 100;
+
+/// Render of the current view, which can be spread over several animation frames
+#[derive(Clone, Debug)]
+struct RenderJob {
+    depth: u32,
+    status: String,
+    /// Where to place extra references once the pass in progress finishes, when rendering with perturbation
+    perturbation: Option<PerturbationJob>,
+}
+
+#[derive(Clone, Debug)]
+struct PerturbationJob {
+    center: (Fixed, Fixed),
+    julia: Option<(f64, f64)>,
+    exponent: i32,
+    delta_transform: Matrix3,
+    extra_references: usize,
+}
 
 #[derive(Clone, Debug)]
 struct App {
     window: Window,
     body: HtmlElement,
     canvas: HtmlCanvasElement,
+    status: HtmlElement,
+    formula_input: HtmlInputElement,
+    polynomial_input: HtmlInputElement,
+    sequence_input: HtmlInputElement,
+    hybrid_input: HtmlInputElement,
+    formula_error: HtmlElement,
     context: WebGl2RenderingContext,
     performance: Performance,
     props: CanvasState,
     viewport: ViewportController,
+    /// Controller of the Julia set on the right in split view, where `viewport` has the Mandelbrot set on the left
+    julia_viewport: Option<ViewportController>,
+    /// Pane under the pointer, which gets all input
+    focus: Pane,
+    /// Pane the render in progress is drawing
+    rendering: Pane,
+    /// Panes which need rendering again, the last one first
+    stale: Vec<Pane>,
+    reference: Option<ReferenceOrbit>,
+    depth: Depth,
+    julia_c: (f64, f64),
+    formula: Formula,
+    /// Source of the last custom formula compiled, drawn when `formula` is [`Formula::Custom`]
+    custom_formula: String,
+    /// Source of the polynomial drawn when `formula` is [`Formula::Newton`]
+    newton_polynomial: String,
+    /// Sequence of A and B rates drawn when `formula` is [`Formula::Lyapunov`]
+    lyapunov_sequence: String,
+    /// Last hybrid compiled, drawn when `formula` is [`Formula::Hybrid`]
+    hybrid: Option<Hybrid>,
+    /// Power z is raised to each iteration
+    power: f64,
+    /// Whether escape-time sets are coloured by a continuous iteration count rather than in bands
+    smooth: bool,
+    /// Whether lines are drawn along the boundary of escape-time sets from an estimate of the distance to them
+    distance_estimation: bool,
+    /// Width of those lines in pixels
+    line_thickness: f64,
+    /// How points inside escape-time sets are coloured
+    interior: Interior,
+    /// Name of the palette escape-time sets are coloured with, one of [`BUILTIN_PALETTES`] or the name of the file it
+    /// was imported from
+    palette_name: String,
+    palette: Palette,
+    /// 3D shape ray marched instead of the 2D sets, using `power` for its own
+    shape: Option<Shape>,
+    /// Iteration limits of the red, green and blue channels when drawing the Buddhabrot instead
+    buddhabrot_depths: Option<[u32; 3]>,
+    /// Buddhabrot being accumulated for the current view
+    buddhabrot: Option<Buddhabrot>,
+    /// Tone mapping of the Buddhabrot, see [`Buddhabrot::image`]
+    exposure: f64,
+    gamma: f64,
+    job: Option<RenderJob>,
+    /// Status of the last render, shown after the mode
+    render_status: String,
+    tile_budget: TileBudget,
+    /// Whether the last render started was a preview drawn while the view was moving, which skips glitch correction
+    /// until [`App::refine`] draws it again
+    previewing: bool,
+    render_requested: bool,
+    /// When the render in progress was started, to time previews with
+    render_started_ms: f64,
+    last_render_ms: f64,
+    last_input_ms: f64,
+    /// Timeout which calls [`App::refine`], if one is waiting
+    refine_timeout: Option<i32>,
     running_animations: u32,
 }
 
+type AppRef = Rc<RefCell<App>>;
+
+/// Applies the text typed into an input, returning an error to show the user
+type InputHandler = fn(&mut App, &str) -> Result<(), String>;
+
 fn dispatch_draw_callback(app_ref: &Rc<RefCell<App>>) -> Result<(), JsValue> {
-    app_ref.borrow().draw();
+    app_ref.borrow_mut().draw();
     Ok(())
 }
 
@@ -50,22 +199,79 @@ impl App {
         let document = window.document().unwrap();
         let body = document.body().unwrap();
         let canvas: HtmlCanvasElement = document.get_element_by_id("root").unwrap().dyn_into()?;
+        let status: HtmlElement = document.get_element_by_id("status").unwrap().dyn_into()?;
+        let formula_input: HtmlInputElement =
+            document.get_element_by_id("formula").unwrap().dyn_into()?;
+        let polynomial_input: HtmlInputElement = document
+            .get_element_by_id("polynomial")
+            .unwrap()
+            .dyn_into()?;
+        let sequence_input: HtmlInputElement =
+            document.get_element_by_id("sequence").unwrap().dyn_into()?;
+        let hybrid_input: HtmlInputElement =
+            document.get_element_by_id("hybrid").unwrap().dyn_into()?;
+        let formula_error: HtmlElement = document
+            .get_element_by_id("formula-error")
+            .unwrap()
+            .dyn_into()?;
         let context: WebGl2RenderingContext = canvas.get_context("webgl2")?.unwrap().dyn_into()?;
         let performance = window.performance().unwrap();
 
-        let props = init_shaders(&context, DEPTH);
+        let props = init_shaders(&context).map_err(|error| JsValue::from_str(&error))?;
+        let palette = Palette::default();
+        set_palette(&context, &props, &palette);
 
         let mut result = Self {
             window,
             body,
             canvas,
+            status,
+            formula_input,
+            polynomial_input,
+            sequence_input,
+            hybrid_input,
+            formula_error,
             context,
             performance,
             props,
             viewport: Default::default(),
+            julia_viewport: None,
+            focus: Pane::Main,
+            rendering: Pane::Main,
+            stale: Vec::new(),
+            reference: None,
+            depth: Default::default(),
+            julia_c: DEFAULT_JULIA_C,
+            formula: Default::default(),
+            custom_formula: String::new(),
+            newton_polynomial: String::new(),
+            lyapunov_sequence: String::new(),
+            hybrid: None,
+            power: 2.0,
+            smooth: false,
+            distance_estimation: false,
+            line_thickness: DEFAULT_LINE_THICKNESS,
+            interior: Interior::Flat,
+            palette_name: String::from(BUILTIN_PALETTES[0]),
+            palette,
+            shape: None,
+            buddhabrot_depths: None,
+            buddhabrot: None,
+            exposure: 1.0,
+            gamma: 2.0,
+            job: None,
+            render_status: String::new(),
+            tile_budget: Default::default(),
+            previewing: false,
+            render_started_ms: 0.0,
+            render_requested: false,
+            last_render_ms: 0.0,
+            last_input_ms: 0.0,
+            refine_timeout: None,
             running_animations: 0,
         };
         result.resize();
+        result.load_params();
         Ok(result)
     }
 
@@ -74,16 +280,889 @@ impl App {
         request_animation_frame(app_ref, &window, dispatch_draw_callback)
     }
 
-    fn draw(&self) {
-        draw(
+    /// Starts rendering every pane at full resolution, abandoning any render still in progress
+    fn draw(&mut self) {
+        self.save_params();
+        self.previewing = false;
+        set_resolution(&self.context, &mut self.props, 1.0);
+        self.stale = match self.julia_viewport {
+            Some(_) => vec![Pane::Julia, Pane::Main],
+            None => vec![Pane::Main],
+        };
+        self.start_render();
+    }
+
+    /// Draws a preview of the focused pane while its view is moving, see [`App::draw_pane_interactive`]
+    fn draw_interactive(&mut self) {
+        self.draw_pane_interactive(self.focus);
+    }
+
+    /// Draws a preview of `pane` at a resolution which keeps up with the input, before any other stale panes
+    ///
+    /// The resolution follows how long previews take to render, see [`App::time_preview`]. Everything is redrawn at
+    /// full resolution once the input has stopped for [`IDLE_MS`]
+    fn draw_pane_interactive(&mut self, pane: Pane) {
+        self.last_input_ms = self.performance.now();
+        self.stale.retain(|stale| *stale != pane);
+        self.stale.push(pane);
+        let depth = self.depth.for_scale(self.pane(pane).scale());
+        let pixels = self.canvas.width() as f64 * self.canvas.height() as f64;
+        let resolution = self.tile_budget.resolution(pixels, depth);
+        set_resolution(&self.context, &mut self.props, resolution);
+        self.previewing = true;
+        self.start_render();
+        if let Some(handle) = self.refine_timeout.take() {
+            self.window.clear_timeout_with_handle(handle);
+        }
+        self.refine_timeout = Some(
+            set_timeout((), &self.window, IDLE_MS as i32, |_| {
+                with_app(App::refine);
+                Ok(())
+            })
+            .unwrap(),
+        );
+    }
+
+    /// Redraws a preview at full resolution with glitch correction if the view has stopped moving
+    fn refine(&mut self) {
+        self.refine_timeout = None;
+        if self.previewing && self.performance.now() - self.last_input_ms >= IDLE_MS {
+            self.draw();
+        }
+    }
+
+    /// Starts rendering the most recently stale pane at the current resolution, abandoning any render still in
+    /// progress
+    fn start_render(&mut self) {
+        self.last_render_ms = self.performance.now();
+        self.render_started_ms = self.last_render_ms;
+        let Some(&pane) = self.stale.last() else {
+            return;
+        };
+        self.rendering = pane;
+        set_region(&mut self.props, pane.region(self.julia_viewport.is_some()));
+        if let Some(depths) = self.buddhabrot_depths {
+            let (width, height) = region_size(&self.props);
+            self.job = None;
+            self.stale.clear();
+            self.buddhabrot = Some(Buddhabrot::new(width, height, depths));
+            self.render();
+            return;
+        }
+        self.buddhabrot = None;
+        let viewport = self.pane(pane);
+        if let (Some(shape), Some(camera)) = (self.shape, viewport.camera()) {
+            let (eye, transform) = (camera.position(), camera.ray_transform(viewport.aspect()));
+            let depth = self.depth.for_scale(viewport.scale());
+            set_tile_size(&mut self.props, self.tile_budget.tile_size(depth));
+            draw_raymarch(
+                &self.context,
+                &mut self.props,
+                shape,
+                self.power,
+                eye,
+                &transform.into(),
+            );
+            self.job = Some(RenderJob {
+                depth,
+                status: String::from("Ray marching"),
+                perturbation: None,
+            });
+            return self.render();
+        }
+        let (scale, center) = (viewport.scale(), viewport.center().clone());
+        let (transform, screen_transform) = (viewport.transform(), viewport.screen_transform());
+        let depth = self.depth.for_scale(scale);
+        set_depth(&self.context, &mut self.props, depth);
+        let julia = self.julia();
+        set_julia(&self.context, &self.props, julia);
+        set_smooth(&self.context, &mut self.props, self.smooth);
+        let thickness = if self.distance_estimation {
+            self.line_thickness
+        } else {
+            0.0
+        };
+        set_line_thickness(&self.context, &mut self.props, thickness);
+        set_interior(&self.context, &mut self.props, self.interior);
+        // A Julia set for a c outside of the escape radius still needs to reach past c to escape
+        let mut bailout = self.formula.bailout(self.power);
+        if self.smooth || self.distance_estimation {
+            bailout = bailout.max(SMOOTH_BAILOUT);
+        }
+        let radius = julia.map_or(bailout, |(re, im)| bailout.max(re.hypot(im)));
+        set_formula(
+            &self.context,
+            &mut self.props,
+            self.formula,
+            self.power,
+            radius,
+        );
+        // Only the single precision program supports other formulas and powers
+        let precision = if self.formula == Formula::Mandelbrot && self.power == 2.0 {
+            Precision::for_scale(scale)
+        } else {
+            Precision::Single
+        };
+        set_tile_size(&mut self.props, self.tile_budget.tile_size(depth));
+        let (status, perturbation) = match precision {
+            Precision::Single => {
+                draw(&self.context, &mut self.props, &transform.into());
+                (String::from("Single precision"), None)
+            }
+            Precision::Double => {
+                draw_double(
+                    &self.context,
+                    &mut self.props,
+                    (center.0.to_f64(), center.1.to_f64()),
+                    scale,
+                    &screen_transform.into(),
+                );
+                (String::from("Double precision"), None)
+            }
+            Precision::Perturbation => {
+                let (status, job) = self.draw_perturbation(depth);
+                (status, Some(job))
+            }
+        };
+        self.job = Some(RenderJob {
+            depth,
+            status,
+            perturbation,
+        });
+        self.render();
+    }
+
+    /// Starts rendering relative to a reference orbit at the centre of the view, recomputing it if the view has moved
+    fn draw_perturbation(&mut self, depth: u32) -> (String, PerturbationJob) {
+        let viewport = self.pane(self.rendering);
+        let (center, scale) = (viewport.center().clone(), viewport.scale());
+        let screen_transform = viewport.screen_transform();
+        let julia = self.julia();
+        if self.reference.as_ref().is_none_or(|reference| {
+            reference.center != center || reference.depth != depth || reference.julia != julia
+        }) {
+            self.reference = Some(ReferenceOrbit::compute(&center, depth, julia));
+        }
+        // Split the scale into a power of two for the shader to track and a mantissa that fits in the transform
+        let exponent = scale.log2().floor() as i32;
+        let mantissa = (scale / 2_f64.powi(exponent)) as f32;
+        let delta_transform = Matrix3::scale(mantissa, mantissa) * screen_transform;
+        let reference = self.reference.as_ref().unwrap();
+        let series = reference.series(exponent, max_delta(&delta_transform));
+        let status = format!(
+            "Perturbation: skipped {} of {} iterations",
+            series.skip,
+            reference.points.len() - 1
+        );
+        upload_orbit(&self.context, &self.props, reference);
+        draw_perturbation(
             &self.context,
-            &self.props,
-            &self.viewport.transform().into(),
+            &mut self.props,
+            &delta_transform.into(),
+            exponent,
+            &series,
+            false,
+        );
+        (
+            status,
+            PerturbationJob {
+                center,
+                julia,
+                exponent,
+                delta_transform,
+                extra_references: 0,
+            },
         )
     }
 
+    /// Starts redrawing glitched pixels relative to an extra reference placed among them, if there are any left
+    fn correct_glitches(&mut self) -> bool {
+        let Some(RenderJob {
+            depth,
+            perturbation: Some(job),
+            ..
+        }) = self.job.as_mut()
+        else {
+            return false;
+        };
+        if job.extra_references >= MAX_EXTRA_REFERENCES {
+            return false;
+        }
+        let (width, height) = region_size(&self.props);
+        let iterations = read_iterations(&self.context, &self.props);
+        let Some((x, y)) = find_glitch(&iterations, width, height) else {
+            return false;
+        };
+        // Offset of the new reference from the centre, in units of 2^exponent
+        let offset = job.delta_transform.transform_point((
+            (x as f32 + 0.5) / width as f32 * 2.0 - 1.0,
+            (y as f32 + 0.5) / height as f32 * 2.0 - 1.0,
+        ));
+        let factor = 2_f64.powi(job.exponent);
+        let precision = job.center.0.precision();
+        let orbit = ReferenceOrbit::compute(
+            &(
+                &job.center.0 + &Fixed::from_f64(offset.0 as f64 * factor, precision),
+                &job.center.1 + &Fixed::from_f64(offset.1 as f64 * factor, precision),
+            ),
+            *depth,
+            job.julia,
+        );
+        let shifted_transform = Matrix3::translate(-offset.0, -offset.1) * job.delta_transform;
+        let series = orbit.series(job.exponent, max_delta(&shifted_transform));
+        upload_orbit(&self.context, &self.props, &orbit);
+        draw_perturbation(
+            &self.context,
+            &mut self.props,
+            &shifted_transform.into(),
+            job.exponent,
+            &series,
+            true,
+        );
+        job.extra_references += 1;
+        true
+    }
+
+    /// Traces Buddhabrot orbits for a frame's worth of time and shows the result, asking to carry on next frame until
+    /// there are enough samples
+    fn render_buddhabrot(&mut self) {
+        let Some(buddhabrot) = self.buddhabrot.as_mut() else {
+            return;
+        };
+        let start = self.performance.now();
+        while self.performance.now() - start < BUDDHABROT_FRAME_MS {
+            buddhabrot.accumulate(
+                BUDDHABROT_BATCH,
+                self.viewport.view(),
+                self.viewport.aspect(),
+            );
+        }
+        let per_pixel = buddhabrot.samples / (buddhabrot.width * buddhabrot.height).max(1) as u64;
+        let mut status = format!("{} samples", buddhabrot.samples);
+        if resolution(&self.props) < 1.0 {
+            status += &format!(" | Preview at {:.1}%", resolution(&self.props) * 100.0);
+        }
+        self.render_status = status;
+        self.present_buddhabrot();
+        if per_pixel < BUDDHABROT_SAMPLES_PER_PIXEL {
+            self.request_render();
+        }
+    }
+
+    /// Tone maps the Buddhabrot onto the canvas
+    fn present_buddhabrot(&self) {
+        if let Some(buddhabrot) = &self.buddhabrot {
+            present_image(
+                &self.context,
+                &self.props,
+                buddhabrot.width,
+                buddhabrot.height,
+                &buddhabrot.image(self.exposure, self.gamma),
+            );
+        }
+        self.show_status();
+    }
+
+    /// Draws the next few tiles of the render in progress, and asks to carry on next frame if there are more
+    fn render(&mut self) {
+        if self.buddhabrot.is_some() {
+            return self.render_buddhabrot();
+        }
+        let Some(job) = &self.job else {
+            return;
+        };
+        let (depth, mut status) = (job.depth, job.status.clone());
+        // Each correction reads back the whole frame and computes another reference, which is too slow for previews
+        let finished = draw_tiles(&self.context, &mut self.props, self.tile_budget.work)
+            && (self.previewing || !self.correct_glitches());
+        present(&self.context, &self.props);
+        if let Some(Some(job)) = self.job.as_ref().map(|job| &job.perturbation) {
+            status += &format!(", {} extra references", job.extra_references);
+        }
+        if resolution(&self.props) < 1.0 {
+            status += &format!(" | Preview at {:.1}%", resolution(&self.props) * 100.0);
+        }
+        if let Some(progress) = pass_progress(&self.props) {
+            status += &format!(" | Rendering {:.0}%", progress * 100.0);
+        }
+        // Ray marching has its own step and iteration limits
+        self.render_status = match self.shape {
+            Some(_) => status,
+            None => format!(
+                "Depth: {depth}{} | {status}",
+                if self.depth.auto { " (auto)" } else { "" }
+            ),
+        };
+        self.show_status();
+        if finished {
+            if self.previewing {
+                let started = self.render_started_ms;
+                request_animation_frame(started, &self.window, |started| {
+                    with_app(|app| app.time_preview(*started));
+                    Ok(())
+                })
+                .unwrap();
+            }
+            self.job = None;
+            self.stale.retain(|stale| *stale != self.rendering);
+            if !self.stale.is_empty() {
+                self.start_render();
+            }
+        } else {
+            self.request_render();
+        }
+    }
+
+    /// Fits the work per frame to a preview started at `started_ms`, if nothing else has started rendering since
+    ///
+    /// This runs on the frame after the preview finished, as the GPU draws in the background and the browser waits
+    /// for it before showing a frame
+    fn time_preview(&mut self, started_ms: f64) {
+        if self.render_started_ms == started_ms {
+            self.tile_budget.update(self.performance.now() - started_ms);
+        }
+    }
+
+    fn request_render(&mut self) {
+        if self.render_requested {
+            return;
+        }
+        self.render_requested = true;
+        request_animation_frame((), &self.window, |_| {
+            with_app(App::continue_render);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    /// Carries on with the render in progress, adapting the amount drawn per frame to how long the last one took
+    fn continue_render(&mut self) {
+        self.render_requested = false;
+        let now = self.performance.now();
+        self.tile_budget.update(now - self.last_render_ms);
+        self.last_render_ms = now;
+        self.render();
+    }
+
+    fn set_status(&self, status: &str) {
+        self.status.set_text_content(Some(status));
+    }
+
+    fn show_status(&self) {
+        if let Some(depths) = self.buddhabrot_depths {
+            let name = if depths.iter().all(|depth| *depth == depths[0]) {
+                "Buddhabrot"
+            } else {
+                "Nebulabrot"
+            };
+            return self.set_status(&format!(
+                "{name} | Depths: {} | Exposure: {:.2}, gamma: {:.1} | {}",
+                depths.map(|depth| depth.to_string()).join(", "),
+                self.exposure,
+                self.gamma,
+                self.render_status
+            ));
+        }
+        if let Some(shape) = self.shape {
+            let power = match shape {
+                Shape::Mandelbulb => "power",
+                Shape::Mandelbox => "scale",
+            };
+            return self.set_status(&format!(
+                "{} | {power} {} | {}",
+                shape.name(),
+                self.power,
+                self.render_status
+            ));
+        }
+        let (re, im) = self.julia_c;
+        let mode = match (self.viewport.mode(), &self.julia_viewport) {
+            (_, Some(_)) => "Mandelbrot + Julia",
+            (Mode::Mandelbrot, None) => "Mandelbrot",
+            (Mode::Julia, None) => "Julia",
+        };
+        let formula = match self.formula {
+            Formula::Custom => format!("z -> {}", self.custom_formula),
+            Formula::Hybrid => format!(
+                "{}, power {}",
+                self.hybrid
+                    .as_ref()
+                    .map_or(String::new(), |hybrid| hybrid.to_string()),
+                self.power
+            ),
+            Formula::Newton => {
+                return self.set_status(&format!(
+                    "Newton | p(z) = {} | {}",
+                    self.newton_polynomial, self.render_status
+                ))
+            }
+            Formula::Lyapunov => {
+                return self.set_status(&format!(
+                    "Lyapunov | {} | {}",
+                    self.lyapunov_sequence, self.render_status
+                ))
+            }
+            formula if self.interior == Interior::Flat => {
+                format!("{}, power {}", formula.name(), self.power)
+            }
+            formula => format!(
+                "{}, power {}, {} interior",
+                formula.name(),
+                self.power,
+                self.interior.name().to_lowercase()
+            ),
+        };
+        self.set_status(&format!(
+            "{mode} | {formula} | c: {re:.6} {im:+.6}i | {}",
+            self.render_status
+        ));
+    }
+
+    /// `c` to draw the Julia set for, if in Julia mode
+    fn julia(&self) -> Option<(f64, f64)> {
+        (self.pane(self.rendering).mode() == Mode::Julia).then_some(self.julia_c)
+    }
+
+    /// Uses the point under `point` as `c` for Julia mode, if the Mandelbrot set is being shown
+    pub fn pick_julia_c(&mut self, point: (f64, f64)) {
+        if self.focus == Pane::Main && self.viewport.mode() == Mode::Mandelbrot {
+            self.julia_c = self.viewport.point_at(point);
+            self.show_status();
+        }
+    }
+
+    /// Changes the power z is raised to each iteration, moving to a view which fits the new set
+    pub fn set_power(&mut self, power: f64) {
+        self.power = power.clamp(MIN_POWER, MAX_POWER);
+        self.viewport
+            .set_view(self.formula.default_view(self.power));
+        self.draw();
+    }
+
+    /// Adds `delta` to the power, rounded to hundredths so repeated steps stay tidy
+    pub fn change_power(&mut self, delta: f64) {
+        self.set_power(((self.power + delta) * 100.0).round() / 100.0);
+    }
+
+    /// Switches to `formula`, moving to a view which fits its set
+    pub fn set_formula(&mut self, formula: Formula) {
+        self.leave_shape();
+        self.formula = formula;
+        self.viewport
+            .set_view(self.formula.default_view(self.power));
+        self.draw();
+    }
+
+    /// Compiles `source`, a formula of `z` and `c` like `z^3 + c*sin(z)`, and switches to it
+    ///
+    /// Errors in the formula or from compiling the generated shader are returned for the user to fix
+    pub fn set_custom_formula(&mut self, source: &str) -> Result<(), String> {
+        let glsl = to_glsl(&parse(source)?)?;
+        compile_custom(&self.context, &mut self.props, &glsl)?;
+        self.custom_formula = source.trim().to_string();
+        self.set_formula(Formula::Custom);
+        Ok(())
+    }
+
+    /// Compiles and switches to `source`, a hybrid like `2x Mandelbrot, 1x Burning Ship abs(im)`, see
+    /// [`Hybrid::parse`]
+    pub fn set_hybrid(&mut self, source: &str) -> Result<(), String> {
+        let hybrid = Hybrid::parse(source)?;
+        compile_hybrid(&self.context, &mut self.props, &hybrid)?;
+        self.hybrid = Some(hybrid);
+        self.set_formula(Formula::Hybrid);
+        Ok(())
+    }
+
+    /// Parameters of the main view to save, if it's showing one of the formulas which can be
+    fn params(&self) -> Option<Params> {
+        if self.shape.is_some()
+            || self.buddhabrot_depths.is_some()
+            || matches!(
+                self.formula,
+                Formula::Custom | Formula::Newton | Formula::Lyapunov
+            )
+        {
+            return None;
+        }
+        Some(Params {
+            view: self.viewport.view().clone(),
+            formula: self.formula,
+            power: self.power,
+            hybrid: self.hybrid.clone(),
+            julia: (self.viewport.mode() == Mode::Julia).then_some(self.julia_c),
+            depth: (!self.depth.auto).then_some(self.depth.limit),
+        })
+    }
+
+    /// Saves the parameters of the view in the URL's hash, or clears it if they can't be saved
+    fn save_params(&self) {
+        let location = self.window.location();
+        let mut url =
+            location.pathname().unwrap_or_default() + &location.search().unwrap_or_default();
+        if let Some(params) = self.params() {
+            let pairs: Vec<_> = params
+                .to_pairs()
+                .into_iter()
+                .map(|(key, value)| format!("{key}={}", js_sys::encode_uri_component(&value)))
+                .collect();
+            url += &format!("#{}", pairs.join("&"));
+        }
+        if let Ok(history) = self.window.history() {
+            history
+                .replace_state_with_url(&JsValue::NULL, "", Some(&url))
+                .ok();
+        }
+    }
+
+    /// Goes to the view saved in the URL's hash by [`App::save_params`], if there is one
+    fn load_params(&mut self) {
+        let hash = self.window.location().hash().unwrap_or_default();
+        let Some(hash) = hash.strip_prefix('#').filter(|hash| !hash.is_empty()) else {
+            return;
+        };
+        let mut pairs = Vec::new();
+        for pair in hash.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match js_sys::decode_uri_component(value) {
+                Ok(value) => pairs.push((key, String::from(value))),
+                Err(_) => return console_log!("Invalid saved view: can't decode {key}"),
+            }
+        }
+        let params =
+            match Params::from_pairs(pairs.iter().map(|(key, value)| (*key, value.as_str()))) {
+                Ok(params) => params,
+                Err(error) => return console_log!("Invalid saved view: {error}"),
+            };
+        if let Some(hybrid) = &params.hybrid {
+            if let Err(error) = compile_hybrid(&self.context, &mut self.props, hybrid) {
+                return console_log!("Invalid saved view: {error}");
+            }
+            self.hybrid_input.set_value(&hybrid.to_string());
+        }
+        self.hybrid = params.hybrid;
+        self.formula = params.formula;
+        self.power = params.power.clamp(MIN_POWER, MAX_POWER);
+        if let Some(limit) = params.depth {
+            self.depth = Depth::fixed(limit);
+        }
+        if let Some(c) = params.julia {
+            // Come back out of the Julia set to the formula's usual view
+            self.viewport
+                .set_view(self.formula.default_view(self.power));
+            self.julia_c = c;
+            self.viewport.toggle_mode();
+        }
+        self.viewport.set_view(params.view);
+    }
+
+    /// Draws Newton's method for `source`, a polynomial in `z` like `z^3 - 1`, framing its roots
+    pub fn set_newton_polynomial(&mut self, source: &str) -> Result<(), String> {
+        let polynomial = Polynomial::from_expression(&parse(source)?)?;
+        if polynomial.degree() < 2 {
+            return Err(String::from("Polynomials need a degree of at least 2"));
+        }
+        let roots = polynomial.roots();
+        set_polynomial(&self.context, &self.props, &polynomial, &roots);
+        self.newton_polynomial = source.trim().to_string();
+        self.leave_shape();
+        self.formula = Formula::Newton;
+        self.viewport.set_view(view_for_roots(&roots));
+        self.draw();
+        Ok(())
+    }
+
+    /// Draws the Lyapunov exponents of the logistic map with its rate cycling through `source`, a sequence of A and B
+    /// like `AABAB`
+    pub fn set_lyapunov_sequence(&mut self, source: &str) -> Result<(), String> {
+        let sequence = parse_sequence(source)?;
+        set_sequence(&self.context, &self.props, &sequence);
+        self.lyapunov_sequence = sequence
+            .iter()
+            .map(|b| if *b { 'B' } else { 'A' })
+            .collect();
+        self.set_formula(Formula::Lyapunov);
+        Ok(())
+    }
+
+    /// Passes the text of `input` to `apply` when it's changed, showing any error under the inputs
+    pub fn input_listener(
+        app_ref: AppRef,
+        input: HtmlInputElement,
+        apply: InputHandler,
+    ) -> impl Fn(Event) -> Result<(), JsValue> {
+        move |_| {
+            let mut app = app_ref.borrow_mut();
+            let error = match apply(&mut app, &input.value()) {
+                Ok(()) => String::new(),
+                Err(error) => error,
+            };
+            app.formula_error.set_text_content(Some(&error));
+            Ok(())
+        }
+    }
+
+    pub fn cycle_formula(&mut self, step: isize) {
+        self.set_formula(self.formula.cycle(step));
+    }
+
+    /// Draws the Buddhabrot with the given iteration limits for the red, green and blue channels, or goes back to the
+    /// escape-time renderer for `None`
+    pub fn set_buddhabrot(&mut self, depths: Option<[u32; 3]>) {
+        self.buddhabrot_depths = depths;
+        if depths.is_some() {
+            self.leave_shape();
+            self.leave_split();
+            self.viewport
+                .set_view(Formula::Mandelbrot.default_view(2.0));
+        }
+        self.draw();
+    }
+
+    /// Switches from the escape-time renderer to the Buddhabrot, then the Nebulabrot, then back again
+    pub fn cycle_buddhabrot(&mut self) {
+        self.set_buddhabrot(match self.buddhabrot_depths {
+            None => Some(BUDDHABROT_DEPTHS),
+            Some(BUDDHABROT_DEPTHS) => Some(NEBULABROT_DEPTHS),
+            Some(_) => None,
+        });
+    }
+
+    /// Sets the tone mapping of the Buddhabrot without starting it again
+    pub fn set_tone_mapping(&mut self, exposure: f64, gamma: f64) {
+        self.exposure = exposure.clamp(0.01, 100.0);
+        self.gamma = gamma.clamp(0.1, 10.0);
+        self.present_buddhabrot();
+    }
+
+    pub fn multiply_exposure(&mut self, factor: f64) {
+        self.set_tone_mapping(self.exposure * factor, self.gamma);
+    }
+
+    /// Adds `delta` to the gamma, rounded to tenths so repeated steps stay tidy
+    pub fn change_gamma(&mut self, delta: f64) {
+        self.set_tone_mapping(self.exposure, ((self.gamma + delta) * 10.0).round() / 10.0);
+    }
+
+    pub fn toggle_julia(&mut self) {
+        // Split view always has one of each, and 3D shapes have no Julia sets
+        if self.julia_viewport.is_some() || self.shape.is_some() {
+            return;
+        }
+        self.viewport.toggle_mode();
+        self.draw();
+    }
+
+    /// Switches between one view and the Mandelbrot set on the left with the Julia set for the point under the
+    /// pointer on the right
+    pub fn set_split(&mut self, split: bool) {
+        if split == self.julia_viewport.is_some() {
+            return;
+        }
+        if split {
+            self.leave_shape();
+            self.viewport
+                .set_view(self.formula.default_view(self.power));
+        }
+        self.julia_viewport = split.then(|| {
+            if self.viewport.mode() == Mode::Julia {
+                self.viewport.toggle_mode();
+            }
+            self.buddhabrot_depths = None;
+            let mut julia_viewport = ViewportController::default();
+            julia_viewport.toggle_mode();
+            julia_viewport
+        });
+        self.focus = Pane::Main;
+        self.resize();
+        self.draw();
+    }
+
+    pub fn toggle_split(&mut self) {
+        self.set_split(self.julia_viewport.is_none());
+    }
+
+    /// Goes back to one view without drawing it
+    fn leave_split(&mut self) {
+        if self.julia_viewport.take().is_some() {
+            self.focus = Pane::Main;
+            self.resize();
+        }
+    }
+
+    /// Ray marches `shape` in 3D with its usual power, or goes back to the 2D sets for `None`
+    pub fn set_shape(&mut self, shape: Option<Shape>) {
+        match shape {
+            Some(shape) => {
+                self.leave_split();
+                self.buddhabrot_depths = None;
+                self.shape = Some(shape);
+                self.power = shape.default_power();
+                self.viewport.set_camera(Some(shape.camera()));
+            }
+            None => self.leave_shape(),
+        }
+        self.viewport
+            .set_view(self.formula.default_view(self.power));
+        self.draw();
+    }
+
+    /// Switches from the 2D sets to the Mandelbulb, then the Mandelbox, then back again
+    pub fn cycle_shape(&mut self) {
+        self.set_shape(match self.shape {
+            None => Some(Shape::Mandelbulb),
+            Some(Shape::Mandelbulb) => Some(Shape::Mandelbox),
+            Some(Shape::Mandelbox) => None,
+        });
+    }
+
+    /// Goes back to the 2D sets without drawing them, resetting the power the shape was using
+    fn leave_shape(&mut self) {
+        if self.shape.take().is_some() {
+            self.viewport.set_camera(None);
+            self.power = 2.0;
+        }
+    }
+
+    /// Shows the Julia set for the point under `point` while the pointer is over the Mandelbrot set in split view
+    pub fn hover(&mut self, point: (f64, f64)) {
+        if self.julia_viewport.is_some() && self.focus == Pane::Main {
+            self.julia_c = self.viewport.point_at(point);
+            self.show_status();
+            self.draw_pane_interactive(Pane::Julia);
+        }
+    }
+
+    /// Multiplies the iteration limit in use by `factor`, switching off automatic depth
+    pub fn multiply_depth(&mut self, factor: f64) {
+        self.depth.multiply(factor, self.viewport.scale());
+        self.draw();
+    }
+
+    /// Colours the escape-time sets with one of [`BUILTIN_PALETTES`], keeping the mapping
+    pub fn set_palette(&mut self, name: &str) -> Result<(), String> {
+        let gradient = builtin(name).ok_or_else(|| format!("Unknown palette '{name}'"))?;
+        self.palette = Palette::new(gradient, self.palette.mapping);
+        self.palette_name = BUILTIN_PALETTES
+            .into_iter()
+            .find(|palette| palette.eq_ignore_ascii_case(name.trim()))
+            .unwrap_or(name)
+            .to_string();
+        self.recolour();
+        Ok(())
+    }
+
+    /// Colours the escape-time sets with a palette file called `file_name`, whose extension gives its [`Format`]
+    pub fn import_palette(&mut self, file_name: &str, source: &str) -> Result<(), String> {
+        let format = Format::from_file_name(file_name)
+            .ok_or("Unknown palette format, expected a .map, .ugr or .ggr file")?;
+        let gradient = format.parse(source)?;
+        self.palette = Palette::new(gradient, self.palette.mapping);
+        self.palette_name = file_name
+            .rsplit_once('.')
+            .map_or(file_name, |(stem, _)| stem)
+            .to_string();
+        self.recolour();
+        Ok(())
+    }
+
+    /// The current palette as a file in the format with `extension`
+    pub fn export_palette(&self, extension: &str) -> Result<String, String> {
+        let format = Format::from_extension(extension).ok_or_else(|| {
+            format!("Unknown palette format '{extension}', expected map, ugr or ggr")
+        })?;
+        Ok(format.write(self.palette.gradient(), &self.palette_name))
+    }
+
+    /// Palette after this one in [`BUILTIN_PALETTES`], wrapping around, or before it when `step` is negative
+    pub fn cycle_palette(&mut self, step: isize) {
+        let index = BUILTIN_PALETTES
+            .iter()
+            .position(|palette| *palette == self.palette_name)
+            .map_or(0, |index| index as isize + step);
+        let name = BUILTIN_PALETTES[index.rem_euclid(BUILTIN_PALETTES.len() as isize) as usize];
+        self.set_palette(name).unwrap();
+    }
+
+    /// Changes how escape ratios are spread over the palette, which needs a finite offset and scale
+    pub fn set_palette_mapping(&mut self, mapping: Mapping) -> Result<(), String> {
+        if !mapping.offset.is_finite() {
+            return Err(format!("Invalid palette offset '{}'", mapping.offset));
+        }
+        if !mapping.scale.is_finite() {
+            return Err(format!("Invalid palette scale '{}'", mapping.scale));
+        }
+        self.palette.mapping = mapping;
+        self.recolour();
+        Ok(())
+    }
+
+    /// Shows the escape-time sets in the current palette without drawing them again
+    fn recolour(&mut self) {
+        set_palette(&self.context, &self.props, &self.palette);
+        if self.buddhabrot.is_none() {
+            present(&self.context, &self.props);
+        }
+    }
+
+    /// Switches between banded and smooth colouring
+    pub fn toggle_smooth(&mut self) {
+        self.smooth = !self.smooth;
+        self.draw();
+    }
+
+    /// Interior mode after this one in [`Interior::ALL`], wrapping around, or before it when `step` is negative
+    pub fn cycle_interior(&mut self, step: isize) {
+        self.interior = self.interior.cycle(step);
+        self.draw();
+    }
+
+    /// Switches the lines along the boundary from distance estimation on or off
+    pub fn toggle_distance_estimation(&mut self) {
+        self.distance_estimation = !self.distance_estimation;
+        self.draw();
+    }
+
+    /// Multiplies the width of the lines from distance estimation by `factor`, keeping it between
+    /// [`MIN_LINE_THICKNESS`] and [`MAX_LINE_THICKNESS`] pixels
+    pub fn multiply_line_thickness(&mut self, factor: f64) {
+        self.line_thickness =
+            (self.line_thickness * factor).clamp(MIN_LINE_THICKNESS, MAX_LINE_THICKNESS);
+        if self.distance_estimation {
+            self.draw();
+        }
+    }
+
+    pub fn toggle_auto_depth(&mut self) {
+        self.depth.auto = !self.depth.auto;
+        self.draw();
+    }
+
     fn setup(app_ref: &Rc<RefCell<App>>) -> Result<(), JsValue> {
         App::dispatch_draw(app_ref.clone())?;
+        let inputs: [(_, InputHandler); 4] = [
+            (
+                app_ref.borrow().formula_input.clone(),
+                App::set_custom_formula,
+            ),
+            (
+                app_ref.borrow().polynomial_input.clone(),
+                App::set_newton_polynomial,
+            ),
+            (
+                app_ref.borrow().sequence_input.clone(),
+                App::set_lyapunov_sequence,
+            ),
+            (app_ref.borrow().hybrid_input.clone(), App::set_hybrid),
+        ];
+        for (input, apply) in inputs {
+            add_event_listener(
+                &input.clone(),
+                "change",
+                App::input_listener(app_ref.clone(), input, apply),
+                true,
+            )?;
+        }
         App::register_viewport_events(app_ref.clone())
     }
 
@@ -101,9 +1180,214 @@ impl App {
     }
 }
 
-static STATE: LazyLock<Fragile<Rc<RefCell<Option<Rc<RefCell<App>>>>>>> =
+/// Largest distance of a corner of the screen from the reference, in the units of `delta_transform`
+fn max_delta(delta_transform: &Matrix3) -> f64 {
+    [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)]
+        .into_iter()
+        .map(|corner| {
+            let (x, y) = delta_transform.transform_point(corner);
+            x.hypot(y) as f64
+        })
+        .fold(0.0, f64::max)
+}
+
+static STATE: LazyLock<Fragile<Rc<RefCell<Option<AppRef>>>>> =
     LazyLock::new(|| Fragile::new(Rc::new(RefCell::new(None))));
 
+/// Runs `callback` against the app once it has loaded
+fn with_app(callback: impl FnOnce(&mut App)) {
+    if let Some(app_ref) = STATE.get().borrow().as_ref() {
+        callback(&mut app_ref.borrow_mut());
+    }
+}
+
+/// Like [`with_app`] for callbacks which can fail, turning their error into an exception, or throwing if the app
+/// isn't running
+fn try_with_app<T>(callback: impl FnOnce(&mut App) -> Result<T, String>) -> Result<T, JsValue> {
+    let mut result = Err(String::from("App isn't running"));
+    with_app(|app| result = callback(app));
+    result.map_err(|error| JsValue::from_str(&error))
+}
+
+/// Fixes the iteration limit, switching off automatic depth
+#[wasm_bindgen]
+pub fn set_depth_limit(limit: u32) {
+    with_app(|app| {
+        app.depth = Depth::fixed(limit);
+        app.draw();
+    });
+}
+
+/// Colours escape-time sets with a built-in palette by name, one of `classic`, `ultra`, `fire`, `ocean`, `rainbow` or
+/// `greyscale`
+#[wasm_bindgen]
+pub fn set_palette_by_name(name: &str) -> Result<(), JsValue> {
+    try_with_app(|app| app.set_palette(name))
+}
+
+/// Colours escape-time sets with the contents of a Fractint `.map`, Ultra Fractal `.ugr` or GIMP `.ggr` palette file,
+/// telling which it is from the extension of `file_name`
+#[wasm_bindgen]
+pub fn import_palette(file_name: &str, contents: &str) -> Result<(), JsValue> {
+    try_with_app(|app| app.import_palette(file_name, contents))
+}
+
+/// Contents of a palette file with the current palette, in the format with the extension `map`, `ugr` or `ggr`
+#[wasm_bindgen]
+pub fn export_palette(extension: &str) -> Result<String, JsValue> {
+    try_with_app(|app| app.export_palette(extension))
+}
+
+/// Spreads escape ratios over the palette, starting at `offset` and going through it `scale` times as fast, either
+/// wrapping around at the end with `repeat` or staying at the last colour
+#[wasm_bindgen]
+pub fn set_palette_mapping(
+    offset: f32,
+    scale: f32,
+    repeat: bool,
+    reverse: bool,
+) -> Result<(), JsValue> {
+    try_with_app(|app| {
+        app.set_palette_mapping(Mapping {
+            offset,
+            scale,
+            repeat,
+            reverse,
+        })
+    })
+}
+
+/// Colours escape-time sets by a continuous iteration count, or in bands of whole iterations
+#[wasm_bindgen]
+pub fn set_smooth_colouring(smooth: bool) {
+    with_app(|app| {
+        app.smooth = smooth;
+        app.draw();
+    });
+}
+
+/// Draws lines `thickness` pixels wide along the boundary of escape-time sets from an estimate of the distance to
+/// them, or turns them off for 0
+#[wasm_bindgen]
+pub fn set_distance_estimation(thickness: f64) {
+    with_app(|app| {
+        app.distance_estimation = thickness > 0.0;
+        if app.distance_estimation {
+            app.line_thickness = thickness.clamp(MIN_LINE_THICKNESS, MAX_LINE_THICKNESS);
+        }
+        app.draw();
+    });
+}
+
+/// Switches automatic depth on or off, which raises the iteration limit as the view zooms in
+#[wasm_bindgen]
+pub fn set_auto_depth(auto: bool) {
+    with_app(|app| {
+        app.depth.auto = auto;
+        app.draw();
+    });
+}
+
+/// Sets the power z is raised to each iteration, which can be fractional
+#[wasm_bindgen]
+pub fn set_multibrot_power(power: f64) -> Result<(), JsValue> {
+    if !power.is_finite() {
+        return Err(JsValue::from_str("Invalid power"));
+    }
+    with_app(|app| app.set_power(power));
+    Ok(())
+}
+
+/// Switches formula by name, one of `mandelbrot`, `burning_ship`, `tricorn`, `celtic` or `buffalo`
+#[wasm_bindgen]
+pub fn set_formula_by_name(name: &str) -> Result<(), JsValue> {
+    let formula = Formula::from_name(name).ok_or_else(|| JsValue::from_str("Unknown formula"))?;
+    with_app(|app| app.set_formula(formula));
+    Ok(())
+}
+
+/// Switches how points inside escape-time sets are coloured by name, one of `flat`, `period`, `multiplier` or
+/// `distance`
+#[wasm_bindgen]
+pub fn set_interior_by_name(name: &str) -> Result<(), JsValue> {
+    let interior =
+        Interior::from_name(name).ok_or_else(|| JsValue::from_str("Unknown interior mode"))?;
+    with_app(|app| {
+        app.interior = interior;
+        app.draw();
+    });
+    Ok(())
+}
+
+/// Draws the Buddhabrot instead of the escape-time renderer, or goes back to it
+#[wasm_bindgen]
+pub fn set_buddhabrot(enabled: bool) {
+    with_app(|app| app.set_buddhabrot(enabled.then_some(BUDDHABROT_DEPTHS)));
+}
+
+/// Draws the Nebulabrot, where the red, green and blue channels only count orbits escaping within their limit
+#[wasm_bindgen]
+pub fn set_nebulabrot_depths(red: u32, green: u32, blue: u32) {
+    let depths = [red, green, blue].map(|depth| depth.clamp(MIN_DEPTH, MAX_DEPTH));
+    with_app(|app| app.set_buddhabrot(Some(depths)));
+}
+
+/// Sets the exposure and gamma of the Buddhabrot, which can be changed while it's accumulating
+#[wasm_bindgen]
+pub fn set_tone_mapping(exposure: f64, gamma: f64) -> Result<(), JsValue> {
+    if !exposure.is_finite() {
+        return Err(JsValue::from_str("Invalid exposure"));
+    }
+    if !gamma.is_finite() {
+        return Err(JsValue::from_str("Invalid gamma"));
+    }
+    with_app(|app| app.set_tone_mapping(exposure, gamma));
+    Ok(())
+}
+
+/// Splits the canvas into the Mandelbrot set and the Julia set for the point under the pointer, or joins it back up
+#[wasm_bindgen]
+pub fn set_split_view(enabled: bool) {
+    with_app(|app| app.set_split(enabled));
+}
+
+/// Ray marches `mandelbulb` or `mandelbox` in 3D, or goes back to the 2D sets for `null`
+#[wasm_bindgen]
+pub fn set_shape(name: Option<String>) -> Result<(), JsValue> {
+    let shape = match name {
+        Some(name) => {
+            Some(Shape::from_name(&name).ok_or_else(|| JsValue::from_str("Unknown shape"))?)
+        }
+        None => None,
+    };
+    with_app(|app| app.set_shape(shape));
+    Ok(())
+}
+
+/// Draws the Lyapunov fractal for a sequence of A and B like `AABAB`, throwing if it has an error
+#[wasm_bindgen]
+pub fn set_lyapunov_sequence(source: &str) -> Result<(), JsValue> {
+    try_with_app(|app| app.set_lyapunov_sequence(source))
+}
+
+/// Compiles and switches to a hybrid such as `2x Mandelbrot, 1x Burning Ship abs(im)`, throwing if it has an error
+#[wasm_bindgen]
+pub fn set_hybrid_formula(source: &str) -> Result<(), JsValue> {
+    try_with_app(|app| app.set_hybrid(source))
+}
+
+/// Draws Newton's method for a polynomial in `z` such as `z^3 - 1`, throwing if it has an error
+#[wasm_bindgen]
+pub fn set_newton_polynomial(source: &str) -> Result<(), JsValue> {
+    try_with_app(|app| app.set_newton_polynomial(source))
+}
+
+/// Compiles and switches to a formula of `z` and `c` such as `z^3 + c*sin(z)`, throwing if it has an error
+#[wasm_bindgen]
+pub fn set_custom_formula(source: &str) -> Result<(), JsValue> {
+    try_with_app(|app| app.set_custom_formula(source))
+}
+
 #[wasm_bindgen(start)]
 pub fn main_js() -> Result<(), JsValue> {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
@@ -0,0 +1,62 @@
+use crate::{
+    fixed::{precision_for_scale, Fixed},
+    view::View,
+};
+
+/// Longest sequence `lyapunov.frag` has room for, must match `MAX_SEQUENCE` there
+pub const MAX_SEQUENCE: usize = 32;
+
+/// Parses a sequence like `"AABAB"`, giving true for each B, which takes the logistic map's rate from b rather than a
+///
+/// Case and whitespace are ignored
+pub fn parse_sequence(source: &str) -> Result<Vec<bool>, String> {
+    let sequence = source
+        .chars()
+        .filter(|char| !char.is_whitespace())
+        .map(|char| match char.to_ascii_uppercase() {
+            'A' => Ok(false),
+            'B' => Ok(true),
+            _ => Err(format!(
+                "Unexpected '{char}', sequences can only use A and B"
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if sequence.is_empty() {
+        return Err(String::from("Sequences need at least one A or B"));
+    }
+    if sequence.len() > MAX_SEQUENCE {
+        return Err(format!(
+            "Sequences can be at most {MAX_SEQUENCE} letters long"
+        ));
+    }
+    Ok(sequence)
+}
+
+/// View of the parameter plane from 2 to 4 along both axes, where the interesting structure is
+pub fn default_view() -> View {
+    let precision = precision_for_scale(1.0);
+    View {
+        center: (
+            Fixed::from_f64(3.0, precision),
+            Fixed::from_f64(3.0, precision),
+        ),
+        scale: 1.0,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lyapunov::parse_sequence;
+
+    #[test]
+    fn parses_sequences() {
+        assert_eq!(
+            parse_sequence("aab AB").unwrap(),
+            vec![false, false, true, false, true]
+        );
+        assert!(parse_sequence("ABC").is_err());
+        assert!(parse_sequence("  ").is_err());
+        assert!(parse_sequence(&"AB".repeat(17)).is_err());
+    }
+}
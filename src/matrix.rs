@@ -15,6 +15,20 @@ impl Matrix3 {
     pub fn translate(x: f32, y: f32) -> Self {
         Self([1.0, 0.0, x, 0.0, 1.0, y, 0.0, 0.0, 1.0])
     }
+
+    /// Applies the transform to the point `(x, y, 1)`
+    pub fn transform_point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            self[0] * x + self[1] * y + self[2],
+            self[3] * x + self[4] * y + self[5],
+        )
+    }
+
+    /// Anticlockwise rotation by `angle` radians
+    pub fn rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self([cos, -sin, 0.0, sin, cos, 0.0, 0.0, 0.0, 1.0])
+    }
 }
 
 impl From<[f32; 9]> for Matrix3 {
@@ -0,0 +1,36 @@
+use crate::view::View;
+
+/// Smallest power the controls can be turned down to, below which the set blows up
+pub const MIN_POWER: f64 = 1.25;
+pub const MAX_POWER: f64 = 16.0;
+
+/// Radius used instead of the escape radius for smooth colouring, must match `SMOOTH_BAILOUT` in `escape.glsl`
+///
+/// The further past the escape radius orbits go, the closer `|z|` grows to a power of the last `|z|` each iteration,
+/// which is what the continuous iteration count assumes
+pub const SMOOTH_BAILOUT: f64 = 256.0;
+
+/// Radius beyond which `z^power + c` escapes, for any `c` within it
+pub fn escape_radius(power: f64) -> f64 {
+    2_f64.powf(1.0 / (power - 1.0))
+}
+
+/// View which fits the whole set for `power`
+pub fn default_view(power: f64) -> View {
+    View {
+        scale: 1.2 * escape_radius(power),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::multibrot::escape_radius;
+
+    #[test]
+    fn radius_shrinks_with_power() {
+        assert_eq!(escape_radius(2.0), 2.0);
+        assert!((escape_radius(3.0) - 2_f64.sqrt()).abs() < 1e-12);
+        assert_eq!(escape_radius(1.5), 4.0);
+    }
+}
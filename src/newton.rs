@@ -0,0 +1,265 @@
+use crate::{
+    complex::{add, div, mul, norm, sub, Complex},
+    expression::{Expression, Operator},
+    fixed::{precision_for_scale, Fixed},
+    view::View,
+};
+
+/// Highest degree of polynomial `newton.frag` has room for, must match `MAX_DEGREE` there
+pub const MAX_DEGREE: usize = 16;
+
+/// Most rounds of refining every root at once before giving up on them converging further
+const MAX_ROOT_ITERATIONS: usize = 1000;
+
+/// Polynomial in `z` with complex coefficients, whose roots Newton's method is drawn converging to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial {
+    /// From the constant term up, without any trailing zeros
+    pub coefficients: Vec<Complex>,
+}
+
+impl Polynomial {
+    fn constant(value: Complex) -> Self {
+        Self {
+            coefficients: vec![value],
+        }
+        .trimmed()
+    }
+
+    fn trimmed(mut self) -> Self {
+        while self.coefficients.last() == Some(&[0.0, 0.0]) {
+            self.coefficients.pop();
+        }
+        self
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    /// Value of a polynomial with no `z` in it
+    fn as_constant(&self) -> Option<Complex> {
+        match self.coefficients.as_slice() {
+            [] => Some([0.0, 0.0]),
+            [value] => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn add(&self, other: &Self, sign: f64) -> Self {
+        let length = self.coefficients.len().max(other.coefficients.len());
+        let term = |polynomial: &Self, n: usize| {
+            polynomial.coefficients.get(n).copied().unwrap_or_default()
+        };
+        Self {
+            coefficients: (0..length)
+                .map(|n| {
+                    let [re, im] = term(other, n);
+                    add(term(self, n), [re * sign, im * sign])
+                })
+                .collect(),
+        }
+        .trimmed()
+    }
+
+    fn mul(&self, other: &Self) -> Result<Self, String> {
+        if self.coefficients.is_empty() || other.coefficients.is_empty() {
+            return Ok(Self::constant([0.0, 0.0]));
+        }
+        if self.degree() + other.degree() > MAX_DEGREE {
+            return Err(format!(
+                "Polynomials can have a degree of at most {MAX_DEGREE}"
+            ));
+        }
+        let mut coefficients =
+            vec![[0.0, 0.0]; self.coefficients.len() + other.coefficients.len() - 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                coefficients[i + j] = add(coefficients[i + j], mul(*a, *b));
+            }
+        }
+        Ok(Self { coefficients }.trimmed())
+    }
+
+    /// Expands an expression of `z` made of numbers, `+ - * /` and whole powers into a polynomial
+    pub fn from_expression(expression: &Expression) -> Result<Self, String> {
+        Ok(match expression {
+            Expression::Number(value) => Self::constant([*value, 0.0]),
+            Expression::Variable(name) => match name.as_str() {
+                "z" => Self {
+                    coefficients: vec![[0.0, 0.0], [1.0, 0.0]],
+                },
+                "i" => Self::constant([0.0, 1.0]),
+                "pi" => Self::constant([std::f64::consts::PI, 0.0]),
+                "e" => Self::constant([std::f64::consts::E, 0.0]),
+                _ => {
+                    return Err(format!(
+                        "Unknown variable '{name}', polynomials can use z, i, pi or e"
+                    ))
+                }
+            },
+            Expression::Negate(inner) => {
+                Self::constant([0.0, 0.0]).add(&Self::from_expression(inner)?, -1.0)
+            }
+            Expression::Binary(operator, left, right) => {
+                let left = Self::from_expression(left)?;
+                let right = Self::from_expression(right)?;
+                match operator {
+                    Operator::Add => left.add(&right, 1.0),
+                    Operator::Subtract => left.add(&right, -1.0),
+                    Operator::Multiply => left.mul(&right)?,
+                    Operator::Divide => match right.as_constant() {
+                        Some(divisor) if divisor != [0.0, 0.0] => Self {
+                            coefficients: left
+                                .coefficients
+                                .iter()
+                                .map(|coefficient| div(*coefficient, divisor))
+                                .collect(),
+                        },
+                        _ => {
+                            return Err(String::from(
+                                "Polynomials can only be divided by non-zero numbers",
+                            ))
+                        }
+                    },
+                    Operator::Power => match right.as_constant() {
+                        Some([exponent, 0.0])
+                            if exponent.fract() == 0.0
+                                && (0.0..=MAX_DEGREE as f64).contains(&exponent) =>
+                        {
+                            let mut result = Self::constant([1.0, 0.0]);
+                            for _ in 0..exponent as usize {
+                                result = result.mul(&left)?;
+                            }
+                            result
+                        }
+                        _ => {
+                            return Err(format!(
+                            "Polynomials can only be raised to whole powers from 0 to {MAX_DEGREE}"
+                        ))
+                        }
+                    },
+                }
+            }
+            Expression::Call(name, _) => {
+                return Err(format!("Polynomials can't use functions like {name}()"))
+            }
+        })
+    }
+
+    pub fn evaluate(&self, z: Complex) -> Complex {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold([0.0, 0.0], |result, coefficient| {
+                add(mul(result, z), *coefficient)
+            })
+    }
+
+    /// Finds every root, repeated ones included, with the Durand-Kerner method
+    pub fn roots(&self) -> Vec<Complex> {
+        let degree = self.degree();
+        if degree == 0 {
+            return Vec::new();
+        }
+        let leading = self.coefficients[degree];
+        // Every root lies within this radius of the origin (Cauchy's bound)
+        let radius = 1.0
+            + self.coefficients[..degree]
+                .iter()
+                .map(|coefficient| norm(div(*coefficient, leading)))
+                .fold(0.0, f64::max);
+        // Starting points have to be spread out and not symmetric about the real axis
+        let mut roots: Vec<Complex> = (0..degree)
+            .map(|n| {
+                let angle = std::f64::consts::TAU * n as f64 / degree as f64 + 0.4;
+                [radius * angle.cos(), radius * angle.sin()]
+            })
+            .collect();
+        for _ in 0..MAX_ROOT_ITERATIONS {
+            let mut change: f64 = 0.0;
+            for n in 0..degree {
+                let others = (0..degree)
+                    .filter(|m| *m != n)
+                    .fold(leading, |product, m| mul(product, sub(roots[n], roots[m])));
+                let step = div(self.evaluate(roots[n]), others);
+                if !step[0].is_finite() || !step[1].is_finite() {
+                    continue;
+                }
+                roots[n] = sub(roots[n], step);
+                change = change.max(norm(step));
+            }
+            if change < 1.0e-14 * radius {
+                break;
+            }
+        }
+        roots
+    }
+}
+
+/// View centred on the roots which shows all of them with room to spare
+pub fn view_for_roots(roots: &[Complex]) -> View {
+    let count = roots.len().max(1) as f64;
+    let center = roots
+        .iter()
+        .fold([0.0, 0.0], |sum, root| add(sum, *root))
+        .map(|sum| sum / count);
+    let spread = roots
+        .iter()
+        .map(|root| norm(sub(*root, center)))
+        .fold(0.0, f64::max);
+    let scale = (spread * 2.0).max(1.0);
+    let precision = precision_for_scale(scale);
+    View {
+        center: (
+            Fixed::from_f64(center[0], precision),
+            Fixed::from_f64(center[1], precision),
+        ),
+        scale,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        complex::{norm, sub},
+        expression::parse,
+        newton::Polynomial,
+    };
+
+    fn polynomial(source: &str) -> Result<Polynomial, String> {
+        Polynomial::from_expression(&parse(source).unwrap())
+    }
+
+    #[test]
+    fn expands_expressions() {
+        assert_eq!(
+            polynomial("(z - 1)(z + i) / 2").unwrap().coefficients,
+            vec![[0.0, -0.5], [-0.5, 0.5], [0.5, 0.0]]
+        );
+        assert_eq!(polynomial("z^2 - z*z + 3").unwrap().degree(), 0);
+        assert!(polynomial("z^17").is_err());
+        assert!(polynomial("z^0.5").is_err());
+        assert!(polynomial("sin(z)").is_err());
+        assert!(polynomial("1 / z").is_err());
+    }
+
+    #[test]
+    fn finds_roots() {
+        let roots = polynomial("z^3 - 1").unwrap().roots();
+        for expected in [
+            [1.0, 0.0],
+            [-0.5, 0.75_f64.sqrt()],
+            [-0.5, -0.75_f64.sqrt()],
+        ] {
+            assert!(roots
+                .iter()
+                .any(|root| norm(sub(*root, expected)) < 1.0e-10));
+        }
+        let cubic = polynomial("2(z - 3)(z + 2i)(z - 1 + i)").unwrap();
+        for root in cubic.roots() {
+            assert!(norm(cubic.evaluate(root)) < 1.0e-9);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! GIMP `.ggr` gradient files, which are a name and a list of segments like
+//!
+//! ```text
+//! GIMP Gradient
+//! Name: Sunrise
+//! 2
+//! 0.000000 0.250000 0.500000 0.000000 0.000000 0.000000 1.000000 1.000000 0.500000 0.000000 1.000000 0 0
+//! 0.500000 0.750000 1.000000 1.000000 0.500000 0.000000 1.000000 1.000000 1.000000 1.000000 1.000000 2 0
+//! ```
+//!
+//! Each segment is its left, middle and right positions, its left and right colours as RGBA from 0 to 1, how it blends
+//! between them and whether it does so in RGB or around the HSV colour wheel. Opacity is ignored
+
+use crate::palette::{Gradient, Interpolation, Stop};
+
+/// Stops each segment blended around the colour wheel is split into
+const HSV_STOPS: usize = 16;
+
+/// Hue from 0 to 1, saturation and value of an RGB colour
+fn to_hsv([red, green, blue]: [f32; 3]) -> [f32; 3] {
+    let max = red.max(green).max(blue);
+    let chroma = max - red.min(green).min(blue);
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == red {
+        ((green - blue) / chroma).rem_euclid(6.0)
+    } else if max == green {
+        (blue - red) / chroma + 2.0
+    } else {
+        (red - green) / chroma + 4.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+    [hue / 6.0, saturation, max]
+}
+
+fn from_hsv([hue, saturation, value]: [f32; 3]) -> [f32; 3] {
+    [5.0, 3.0, 1.0].map(|n| {
+        let k = (n + hue * 6.0) % 6.0;
+        value - value * saturation * k.min(4.0 - k).clamp(0.0, 1.0)
+    })
+}
+
+/// Colour `t` of the way from `left` to `right` around the colour wheel, with increasing hue if `increasing` is set
+fn blend_hsv(left: [f32; 3], right: [f32; 3], t: f32, increasing: bool) -> [f32; 3] {
+    let (left, right) = (to_hsv(left), to_hsv(right));
+    let mut hue_change = right[0] - left[0];
+    if increasing && hue_change < 0.0 {
+        hue_change += 1.0;
+    } else if !increasing && hue_change > 0.0 {
+        hue_change -= 1.0;
+    }
+    from_hsv([
+        (left[0] + hue_change * t).rem_euclid(1.0),
+        left[1] + (right[1] - left[1]) * t,
+        left[2] + (right[2] - left[2]) * t,
+    ])
+}
+
+/// Reads the segments of a gradient into stops
+///
+/// Curved, sine and spherical blends are all drawn as [`Interpolation::Smooth`], and a middle away from the centre
+/// of a segment becomes a stop halfway between its colours
+pub fn parse(source: &str) -> Result<Gradient, String> {
+    let mut lines = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    if lines.next() != Some("GIMP Gradient") {
+        return Err(String::from(
+            "Not a GIMP gradient, expected it to start with 'GIMP Gradient'",
+        ));
+    }
+    let mut line = lines.next();
+    if line.is_some_and(|line| line.starts_with("Name:")) {
+        line = lines.next();
+    }
+    let count: usize = line
+        .and_then(|line| line.parse().ok())
+        .ok_or("Expected the number of segments after the name")?;
+    let mut stops: Vec<Stop> = Vec::new();
+    for segment in 1..=count {
+        let invalid =
+            || format!("Segment {segment}: expected 3 positions, 2 RGBA colours and 2 types");
+        let values = lines
+            .next()
+            .ok_or_else(|| format!("Expected {count} segments, found {}", segment - 1))?
+            .split_whitespace()
+            .map(|value| value.parse::<f32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() < 13 {
+            return Err(invalid());
+        }
+        let [left, middle, right] = [values[0], values[1], values[2]];
+        let left_colour = [values[3], values[4], values[5]];
+        let right_colour = [values[7], values[8], values[9]];
+        let interpolation = match values[11] as i32 {
+            0 => Interpolation::Linear,
+            1..=4 => Interpolation::Smooth,
+            5 => Interpolation::Step,
+            _ => {
+                return Err(format!(
+                    "Segment {segment}: unknown blending type {}",
+                    values[11]
+                ))
+            }
+        };
+        let colouring = values[12] as i32;
+        if !(0..=2).contains(&colouring) {
+            return Err(format!(
+                "Segment {segment}: unknown colouring type {colouring}"
+            ));
+        }
+        // The previous segment only needs its own end if it doesn't meet this one
+        if let Some(end) = stops.pop() {
+            let meets = end.position == left && end.colour == left_colour;
+            if !meets {
+                stops.push(end);
+            }
+        }
+        let stop = |position, colour| Stop {
+            position,
+            colour,
+            interpolation,
+        };
+        if colouring != 0 {
+            stops.extend((0..HSV_STOPS).map(|n| {
+                let t = n as f32 / HSV_STOPS as f32;
+                stop(
+                    left + (right - left) * t,
+                    blend_hsv(left_colour, right_colour, t, colouring == 1),
+                )
+            }));
+        } else if interpolation == Interpolation::Step {
+            // Steps switch colour at the middle
+            stops.push(stop(left, left_colour));
+            stops.push(stop(middle, right_colour));
+        } else {
+            stops.push(stop(left, left_colour));
+            if (middle - (left + right) / 2.0).abs() > 1.0e-6 {
+                let halfway =
+                    [0, 1, 2].map(|channel| (left_colour[channel] + right_colour[channel]) / 2.0);
+                stops.push(stop(middle, halfway));
+            }
+        }
+        stops.push(stop(right, right_colour));
+    }
+    if stops.is_empty() {
+        return Err(String::from("Gradients need at least one segment"));
+    }
+    Gradient::new(stops)
+        .map_err(|error| format!("{error}, segments need to go in order from 0 to 1"))
+}
+
+/// Writes a gradient called `name` with a segment between each pair of stops, and segments of one colour before the
+/// first stop and after the last
+pub fn write(gradient: &Gradient, name: &str) -> String {
+    let stops = &gradient.stops;
+    let mut segments = Vec::new();
+    let (first, last) = (&stops[0], &stops[stops.len() - 1]);
+    if first.position > 0.0 {
+        segments.push((0.0, first.position, first.colour, first.colour, 0));
+    }
+    for pair in stops.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        if from.position == to.position {
+            continue;
+        }
+        segments.push(match from.interpolation {
+            Interpolation::Linear => (from.position, to.position, from.colour, to.colour, 0),
+            Interpolation::Smooth => (from.position, to.position, from.colour, to.colour, 2),
+            // GIMP's steps switch at the middle, so hold the colour with a segment of one colour instead
+            Interpolation::Step => (from.position, to.position, from.colour, from.colour, 0),
+        });
+    }
+    if last.position < 1.0 {
+        segments.push((last.position, 1.0, last.colour, last.colour, 0));
+    }
+    let mut source = format!("GIMP Gradient\nName: {name}\n{}\n", segments.len());
+    for (left, right, left_colour, right_colour, blend) in segments {
+        let [r0, g0, b0] = left_colour;
+        let [r1, g1, b1] = right_colour;
+        source += &format!(
+            "{left:.6} {:.6} {right:.6} {r0:.6} {g0:.6} {b0:.6} 1.000000 {r1:.6} {g1:.6} {b1:.6} 1.000000 {blend} 0\n",
+            (left + right) / 2.0
+        );
+    }
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{builtin, ggr, Gradient, Interpolation, Stop};
+
+    #[test]
+    fn round_trips() {
+        for name in ["ultra", "ocean", "fire"] {
+            let gradient = builtin(name).unwrap();
+            let source = ggr::write(&gradient, name);
+            assert!(source.starts_with(&format!("GIMP Gradient\nName: {name}\n")));
+            let parsed = ggr::parse(&source).unwrap();
+            assert_eq!(parsed.stops.len(), gradient.stops.len());
+            assert_eq!(parsed.bake(), gradient.bake());
+        }
+        // Jumps between colours and steps need extra segments
+        let stop = |position, grey, interpolation| Stop {
+            position,
+            colour: [grey; 3],
+            interpolation,
+        };
+        let gradient = Gradient::new(vec![
+            stop(0.25, 0.0, Interpolation::Step),
+            stop(0.5, 1.0, Interpolation::Linear),
+            stop(0.5, 0.5, Interpolation::Linear),
+            stop(0.75, 0.0, Interpolation::Linear),
+        ])
+        .unwrap();
+        let source = ggr::write(&gradient, "Jumps");
+        assert_eq!(source.lines().nth(2), Some("4"));
+        assert_eq!(ggr::parse(&source).unwrap().bake(), gradient.bake());
+    }
+
+    #[test]
+    fn reads_middles_and_hsv() {
+        let gradient = ggr::parse(
+            "GIMP Gradient\nName: Test\n2\n\
+             0 0.1 0.5 0 0 0 1 1 1 1 1 0 0\n\
+             0.5 0.75 1 1 0 0 1 0 0 1 1 0 2\n",
+        )
+        .unwrap();
+        assert_eq!(gradient.colour_at(0.1), [0.5; 3]);
+        // Clockwise from red to blue goes through magenta rather than green
+        let [red, green, blue] = gradient.colour_at(0.75);
+        assert!(red > 0.9 && green < 0.1 && blue > 0.9);
+        assert_eq!(gradient.colour_at(1.0), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(ggr::parse("GIMP Palette\n").is_err());
+        assert_eq!(
+            ggr::parse("GIMP Gradient\nName: A\n2\n0 0.5 1 0 0 0 1 1 1 1 1 0 0\n"),
+            Err(String::from("Expected 2 segments, found 1"))
+        );
+        assert_eq!(
+            ggr::parse("GIMP Gradient\n1\n0 0.5 1 0 0 0 1 1 1 1\n"),
+            Err(String::from(
+                "Segment 1: expected 3 positions, 2 RGBA colours and 2 types"
+            ))
+        );
+        assert!(ggr::parse("GIMP Gradient\nName: A\nmany\n").is_err());
+        assert!(ggr::parse("GIMP Gradient\n1\n0 0.5 1 0 0 0 1 1 1 1 1 9 0\n").is_err());
+        assert!(ggr::parse(
+            "GIMP Gradient\n2\n0.5 0.75 1 0 0 0 1 1 1 1 1 0 0\n0 0.25 0.5 0 0 0 1 1 1 1 1 0 0\n"
+        )
+        .is_err());
+    }
+}
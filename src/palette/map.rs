@@ -0,0 +1,80 @@
+//! Fractint `.map` files, which list up to 256 colours as lines of `red green blue` from 0 to 255, each optionally
+//! followed by a comment
+
+use crate::palette::{to_byte, Gradient, Interpolation};
+
+/// Colours a map has, which [`write`] samples gradients at
+const MAP_SIZE: usize = 256;
+
+/// Reads the colours of a map as a gradient with them spaced evenly from 0 to 1
+pub fn parse(source: &str) -> Result<Gradient, String> {
+    let mut colours = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let mut values = line.split_whitespace().peekable();
+        if values.peek().is_none() {
+            continue;
+        }
+        let mut channel = || values.next().and_then(|value| value.parse::<u8>().ok());
+        match [channel(), channel(), channel()] {
+            [Some(red), Some(green), Some(blue)] => colours.push([red, green, blue]),
+            _ => {
+                return Err(format!(
+                    "Line {}: expected red, green and blue from 0 to 255",
+                    index + 1
+                ))
+            }
+        }
+    }
+    if colours.is_empty() {
+        return Err(String::from("Maps need at least one colour"));
+    }
+    if colours.len() > MAP_SIZE {
+        return Err(format!(
+            "Maps can have at most {MAP_SIZE} colours, this one has {}",
+            colours.len()
+        ));
+    }
+    Ok(Gradient::even(&colours, Interpolation::Linear))
+}
+
+/// Samples a gradient at 256 evenly spaced points from 0 to 1
+pub fn write(gradient: &Gradient) -> String {
+    (0..MAP_SIZE)
+        .map(|index| {
+            let colour = gradient
+                .colour_at(index as f32 / (MAP_SIZE - 1) as f32)
+                .map(to_byte);
+            format!("{:3} {:3} {:3}\n", colour[0], colour[1], colour[2])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{builtin, map};
+
+    #[test]
+    fn round_trips() {
+        let source = map::write(&builtin("fire").unwrap());
+        assert_eq!(source.lines().count(), 256);
+        assert_eq!(source.lines().next(), Some("  0   0   0"));
+        let gradient = map::parse(&source).unwrap();
+        assert_eq!(map::write(&gradient), source);
+        assert_eq!(map::parse(&map::write(&gradient)), Ok(gradient));
+    }
+
+    #[test]
+    fn reads_comments_and_rejects_bad_lines() {
+        let gradient = map::parse("0 0 0 black\n\n255 128 0 ; orange\n").unwrap();
+        assert_eq!(gradient.colour_at(0.5), [0.5, 128.0 / 510.0, 0.0]);
+        assert_eq!(
+            map::parse("0 0 0\n255 128\n"),
+            Err(String::from(
+                "Line 2: expected red, green and blue from 0 to 255"
+            ))
+        );
+        assert!(map::parse("0 0 256").is_err());
+        assert!(map::parse("").is_err());
+        assert!(map::parse(&"1 2 3\n".repeat(257)).is_err());
+    }
+}
@@ -0,0 +1,414 @@
+//! Gradients which colour the escape-time sets, and the palette files they can be imported from and exported to
+
+mod ggr;
+mod map;
+mod ugr;
+
+/// Width of the texture gradients are baked into
+pub const PALETTE_SIZE: usize = 1024;
+
+/// Names of the palettes [`builtin`] knows, in the order they're cycled through
+pub const BUILTIN_PALETTES: [&str; 6] =
+    ["Classic", "Ultra", "Fire", "Ocean", "Rainbow", "Greyscale"];
+
+/// How the colour changes between a stop and the next one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    /// Eases in and out of each stop, so there are no sharp corners where stops meet
+    Smooth,
+    /// Keeps the stop's colour until the next one
+    Step,
+}
+
+/// Colour at a position from 0 to 1 along a [`Gradient`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stop {
+    pub position: f32,
+    /// Red, green and blue from 0 to 1
+    pub colour: [f32; 3],
+    /// How to get from this stop to the next
+    pub interpolation: Interpolation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<Stop>,
+}
+
+/// How escape ratios are spread over a palette, see `colour()` in `colour.glsl`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mapping {
+    /// Position of an escape ratio of 0
+    pub offset: f32,
+    /// Length of the palette in escape ratios is `1 / scale`
+    pub scale: f32,
+    /// Whether positions past the end wrap around to the start instead of staying at the last colour
+    pub repeat: bool,
+    pub reverse: bool,
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Self {
+            offset: 0.0,
+            scale: 1.0,
+            repeat: false,
+            reverse: false,
+        }
+    }
+}
+
+impl Mapping {
+    /// Port of the position `colour()` in `colour.glsl` looks up an escape ratio at
+    pub fn position(&self, ratio: f32) -> f32 {
+        let position = ratio * self.scale + self.offset;
+        let position = if self.repeat {
+            position - position.floor()
+        } else {
+            position.clamp(0.0, 1.0)
+        };
+        if self.reverse {
+            1.0 - position
+        } else {
+            position
+        }
+    }
+}
+
+impl Gradient {
+    /// Gradient through `stops`, which need to be in order from 0 to 1
+    pub fn new(stops: Vec<Stop>) -> Result<Self, String> {
+        if stops.is_empty() {
+            return Err(String::from("Gradients need at least one stop"));
+        }
+        if stops
+            .iter()
+            .any(|stop| !(0.0..=1.0).contains(&stop.position))
+        {
+            return Err(String::from("Stops need to be between 0 and 1"));
+        }
+        if stops
+            .windows(2)
+            .any(|pair| pair[0].position > pair[1].position)
+        {
+            return Err(String::from("Stops need to be in order"));
+        }
+        Ok(Self { stops })
+    }
+
+    /// Gradient through `colours` spaced evenly from 0 to 1, given as bytes like `#rrggbb`
+    fn even(colours: &[[u8; 3]], interpolation: Interpolation) -> Self {
+        let last = (colours.len() - 1).max(1) as f32;
+        Self {
+            stops: colours
+                .iter()
+                .enumerate()
+                .map(|(index, colour)| Stop {
+                    position: index as f32 / last,
+                    colour: colour.map(|channel| channel as f32 / 255.0),
+                    interpolation,
+                })
+                .collect(),
+        }
+    }
+
+    /// Colour at `position` from 0 to 1, which is the first or last stop's colour beyond them
+    pub fn colour_at(&self, position: f32) -> [f32; 3] {
+        let next = self.stops.iter().position(|stop| stop.position > position);
+        let (from, to) = match next {
+            None => return self.stops[self.stops.len() - 1].colour,
+            Some(0) => return self.stops[0].colour,
+            Some(next) => (&self.stops[next - 1], &self.stops[next]),
+        };
+        let t = (position - from.position) / (to.position - from.position);
+        let t = match from.interpolation {
+            Interpolation::Linear => t,
+            Interpolation::Smooth => t * t * (3.0 - 2.0 * t),
+            Interpolation::Step => 0.0,
+        };
+        [0, 1, 2]
+            .map(|channel| from.colour[channel] + (to.colour[channel] - from.colour[channel]) * t)
+    }
+
+    /// RGBA bytes of the texture `colour.glsl` samples, taking each texel's colour from its middle
+    pub fn bake(&self) -> Vec<u8> {
+        (0..PALETTE_SIZE)
+            .flat_map(|texel| {
+                let [red, green, blue] = self.colour_at((texel as f32 + 0.5) / PALETTE_SIZE as f32);
+                [to_byte(red), to_byte(green), to_byte(blue), 255]
+            })
+            .collect()
+    }
+}
+
+/// Clamps a colour channel like writing to a normalised texture does
+fn to_byte(channel: f32) -> u8 {
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// The original colour scheme, which spins the hue faster and faster as the ratio goes up
+fn classic(ratio: f32) -> [f32; 3] {
+    let hue = (ratio * 360.0).powf(1.5) % 360.0;
+    let saturation = 100.0;
+    let value = ratio * 100.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / (60.0 / 360.0)) % 2.0 - 1.0).abs());
+    let m = value - chroma;
+    let red = if !(60.0..300.0).contains(&hue) {
+        chroma
+    } else if !(120.0..240.0).contains(&hue) {
+        x
+    } else {
+        0.0
+    };
+    let green = if hue < 60.0 || (180.0..240.0).contains(&hue) {
+        x
+    } else if hue < 180.0 {
+        chroma
+    } else {
+        0.0
+    };
+    let blue = if hue < 120.0 {
+        0.0
+    } else if !(180.0..300.0).contains(&hue) {
+        x
+    } else {
+        chroma
+    };
+    [red + m, green + m, blue + m].map(|channel| channel.clamp(0.0, 1.0))
+}
+
+/// One of [`BUILTIN_PALETTES`], ignoring case
+pub fn builtin(name: &str) -> Option<Gradient> {
+    let name = BUILTIN_PALETTES
+        .into_iter()
+        .find(|palette| palette.eq_ignore_ascii_case(name.trim()))?;
+    Some(match name {
+        // A stop for every texel, as it changes too quickly for a few stops to follow, starting at the edges of the
+        // texels so the first is black
+        "Classic" => Gradient {
+            stops: (0..PALETTE_SIZE)
+                .map(|texel| {
+                    let position = texel as f32 / PALETTE_SIZE as f32;
+                    Stop {
+                        position,
+                        colour: classic(position),
+                        interpolation: Interpolation::Step,
+                    }
+                })
+                .collect(),
+        },
+        "Ultra" => Gradient::new(
+            [
+                (0.0, [0, 7, 100]),
+                (0.16, [32, 107, 203]),
+                (0.42, [237, 255, 255]),
+                (0.6425, [255, 170, 0]),
+                (0.8575, [0, 2, 0]),
+                (1.0, [0, 7, 100]),
+            ]
+            .into_iter()
+            .map(|(position, colour)| Stop {
+                position,
+                colour: colour.map(|channel: u8| channel as f32 / 255.0),
+                interpolation: Interpolation::Smooth,
+            })
+            .collect(),
+        )
+        .unwrap(),
+        "Fire" => Gradient::even(
+            &[
+                [0, 0, 0],
+                [128, 0, 0],
+                [255, 64, 0],
+                [255, 192, 0],
+                [255, 255, 224],
+            ],
+            Interpolation::Linear,
+        ),
+        "Ocean" => Gradient::even(
+            &[
+                [0, 8, 32],
+                [0, 64, 128],
+                [0, 160, 176],
+                [160, 240, 232],
+                [255, 255, 255],
+            ],
+            Interpolation::Smooth,
+        ),
+        "Rainbow" => Gradient::even(
+            &[
+                [255, 0, 0],
+                [255, 255, 0],
+                [0, 255, 0],
+                [0, 255, 255],
+                [0, 0, 255],
+                [255, 0, 255],
+                [255, 0, 0],
+            ],
+            Interpolation::Linear,
+        ),
+        _ => Gradient::even(&[[0, 0, 0], [255, 255, 255]], Interpolation::Linear),
+    })
+}
+
+/// Palette file formats gradients can be imported from and exported to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Fractint `.map`
+    Map,
+    /// Ultra Fractal `.ugr`
+    Ugr,
+    /// GIMP `.ggr`
+    Ggr,
+}
+
+impl Format {
+    pub const ALL: [Self; 3] = [Self::Map, Self::Ugr, Self::Ggr];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Map => "map",
+            Format::Ugr => "ugr",
+            Format::Ggr => "ggr",
+        }
+    }
+
+    /// Parses an extension like `"ggr"` or `".GGR"`, ignoring case
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.trim().trim_start_matches('.');
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Format of a file going by the extension of its name
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        Self::from_extension(name.rsplit_once('.')?.1)
+    }
+
+    pub fn parse(self, source: &str) -> Result<Gradient, String> {
+        match self {
+            Format::Map => map::parse(source),
+            Format::Ugr => ugr::parse(source),
+            Format::Ggr => ggr::parse(source),
+        }
+    }
+
+    /// Writes a gradient in this format, calling it `name` in the formats which store one
+    pub fn write(self, gradient: &Gradient, name: &str) -> String {
+        match self {
+            Format::Map => map::write(gradient),
+            Format::Ugr => ugr::write(gradient, name),
+            Format::Ggr => ggr::write(gradient, name),
+        }
+    }
+}
+
+/// Gradient baked into a texture and how escape ratios are spread over it, which together colour the escape-time sets
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    gradient: Gradient,
+    texels: Vec<u8>,
+    pub mapping: Mapping,
+}
+
+impl Palette {
+    pub fn new(gradient: Gradient, mapping: Mapping) -> Self {
+        Self {
+            texels: gradient.bake(),
+            gradient,
+            mapping,
+        }
+    }
+
+    pub fn gradient(&self) -> &Gradient {
+        &self.gradient
+    }
+
+    /// RGBA bytes of the texture, see [`Gradient::bake`]
+    pub fn texels(&self) -> &[u8] {
+        &self.texels
+    }
+
+    /// Port of `colour()` in `colour.glsl`, returning RGBA bytes
+    pub fn colour(&self, ratio: f32) -> [u8; 4] {
+        // Nearest texel, clamped to the edge
+        let texel =
+            ((self.mapping.position(ratio) * PALETTE_SIZE as f32) as usize).min(PALETTE_SIZE - 1);
+        self.texels[texel * 4..texel * 4 + 4].try_into().unwrap()
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new(builtin(BUILTIN_PALETTES[0]).unwrap(), Mapping::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{
+        builtin, Gradient, Interpolation, Mapping, Palette, Stop, BUILTIN_PALETTES,
+    };
+
+    fn stop(position: f32, grey: f32, interpolation: Interpolation) -> Stop {
+        Stop {
+            position,
+            colour: [grey; 3],
+            interpolation,
+        }
+    }
+
+    #[test]
+    fn interpolates_between_stops() {
+        let gradient = Gradient::new(vec![
+            stop(0.2, 0.0, Interpolation::Linear),
+            stop(0.6, 1.0, Interpolation::Step),
+            stop(0.8, 0.0, Interpolation::Smooth),
+            stop(1.0, 1.0, Interpolation::Linear),
+        ])
+        .unwrap();
+        assert_eq!(gradient.colour_at(0.0), [0.0; 3]);
+        assert_eq!(gradient.colour_at(0.3), [0.25; 3]);
+        assert_eq!(gradient.colour_at(0.7), [1.0; 3]);
+        assert!((gradient.colour_at(0.85)[0] - 0.15625).abs() < 1e-6);
+        assert_eq!(gradient.colour_at(1.0), [1.0; 3]);
+        assert!(Gradient::new(vec![]).is_err());
+        assert!(Gradient::new(vec![
+            stop(0.5, 0.0, Interpolation::Linear),
+            stop(0.2, 1.0, Interpolation::Linear),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn maps_ratios() {
+        let mut mapping = Mapping {
+            offset: 0.5,
+            scale: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(mapping.position(0.1), 0.7);
+        assert_eq!(mapping.position(0.5), 1.0);
+        mapping.repeat = true;
+        assert_eq!(mapping.position(0.5), 0.5);
+        mapping.reverse = true;
+        assert_eq!(mapping.position(0.0), 0.5);
+    }
+
+    #[test]
+    fn classic_palette() {
+        let palette = Palette::default();
+        assert_eq!(palette.texels().len(), 1024 * 4);
+        // Escaped immediately is black
+        assert_eq!(palette.colour(0.0), [0, 0, 0, 255]);
+        // hue = 180^1.5 % 360 = 254.9..., so blue gets the chroma of 5000 and the others are pulled below zero by m
+        assert_eq!(palette.colour(0.5), [0, 0, 255, 255]);
+        for name in BUILTIN_PALETTES {
+            assert!(builtin(&name.to_lowercase()).is_some());
+        }
+        assert_eq!(builtin("Plasma"), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! Ultra Fractal `.ugr` gradient files, which hold named entries like
+//!
+//! ```text
+//! Ultra {
+//! gradient:
+//!   title="Ultra" smooth=yes
+//!   index=0 color=6555392
+//!   index=64 color=13331232
+//! opacity:
+//!   smooth=no index=0 opacity=255
+//! }
+//! ```
+//!
+//! Colours are `0xBBGGRR` at indices from 0 to 399 around a gradient which wraps from the last back to the first
+
+use crate::palette::{to_byte, Gradient, Interpolation, Stop};
+
+/// Indices around a gradient
+const UGR_SIZE: i64 = 400;
+
+/// Splits `key=value` pairs, where values can be quoted to hold spaces
+fn pairs(source: &str) -> Result<Vec<(&str, &str)>, String> {
+    let mut pairs = Vec::new();
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once('=').ok_or_else(|| {
+            format!(
+                "Expected key=value, found '{}'",
+                rest.split_whitespace().next().unwrap_or(rest)
+            )
+        })?;
+        if key.contains(char::is_whitespace) {
+            return Err(format!(
+                "Expected key=value, found '{}'",
+                key.split_whitespace().next().unwrap_or(key)
+            ));
+        }
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => quoted
+                .split_once('"')
+                .ok_or_else(|| format!("Unclosed quote in {key}"))?,
+            None => after.split_at(after.find(char::is_whitespace).unwrap_or(after.len())),
+        };
+        pairs.push((key, value));
+        rest = after.trim_start();
+    }
+    Ok(pairs)
+}
+
+/// Offset of the first `marker` in `source` which isn't inside a quoted value
+fn find_unquoted(source: &str, marker: &str) -> Option<usize> {
+    let mut quoted = false;
+    source.char_indices().find_map(|(offset, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (!quoted && source[offset..].starts_with(marker)).then_some(offset)
+    })
+}
+
+/// Reads the first gradient in a file, spreading the indices over 0 to 1 and adding stops at either end with the
+/// colour it wraps around through
+pub fn parse(source: &str) -> Result<Gradient, String> {
+    let start = find_unquoted(source, "gradient:").ok_or("No gradient: section found")?;
+    let body = &source[start + "gradient:".len()..];
+    let end = ["opacity:", "}"]
+        .into_iter()
+        .filter_map(|marker| find_unquoted(body, marker))
+        .min()
+        .unwrap_or(body.len());
+    let mut interpolation = Interpolation::Linear;
+    let mut index = None;
+    let mut points = Vec::new();
+    for (key, value) in pairs(&body[..end])? {
+        match key {
+            "smooth" => {
+                interpolation = match value {
+                    "yes" => Interpolation::Smooth,
+                    "no" => Interpolation::Linear,
+                    _ => return Err(format!("Expected smooth to be yes or no, found '{value}'")),
+                }
+            }
+            "index" => {
+                index = Some(
+                    value
+                        .parse::<i64>()
+                        .map_err(|_| format!("Invalid index '{value}'"))?,
+                )
+            }
+            "color" => {
+                let colour = value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|colour| *colour <= 0xffffff)
+                    .ok_or_else(|| format!("Invalid color '{value}'"))?;
+                let index = index
+                    .take()
+                    .ok_or_else(|| format!("color={value} needs an index before it"))?;
+                points.push((
+                    index.rem_euclid(UGR_SIZE),
+                    [colour, colour >> 8, colour >> 16]
+                        .map(|channel| (channel & 0xff) as f32 / 255.0),
+                ));
+            }
+            _ => {}
+        }
+    }
+    if points.is_empty() {
+        return Err(String::from("Gradients need at least one colour"));
+    }
+    points.sort_by_key(|(index, _)| *index);
+    let (first, last) = (points[0], points[points.len() - 1]);
+    // Colour where the gradient wraps from the last index back round to the first
+    let wrap = if first.0 == 0 {
+        first.1
+    } else {
+        let t = (UGR_SIZE - last.0) as f32 / (first.0 + UGR_SIZE - last.0) as f32;
+        [0, 1, 2].map(|channel| last.1[channel] + (first.1[channel] - last.1[channel]) * t)
+    };
+    let stop = |position, colour| Stop {
+        position,
+        colour,
+        interpolation,
+    };
+    let mut stops = Vec::with_capacity(points.len() + 2);
+    if first.0 != 0 {
+        stops.push(stop(0.0, wrap));
+    }
+    stops.extend(
+        points
+            .into_iter()
+            .map(|(index, colour)| stop(index as f32 / UGR_SIZE as f32, colour)),
+    );
+    stops.push(stop(1.0, wrap));
+    Gradient::new(stops)
+}
+
+/// Writes a gradient as an entry called `name`, rounding stops to the nearest index
+///
+/// Stops at the very end are left out, as the gradient wraps back round to the start there. It's smooth if all of
+/// its stops are
+pub fn write(gradient: &Gradient, name: &str) -> String {
+    let title = name.replace('"', "");
+    let name = title.replace(['{', '}'], "");
+    let smooth = gradient
+        .stops
+        .iter()
+        .all(|stop| stop.interpolation == Interpolation::Smooth);
+    let mut source = format!(
+        "{name} {{\ngradient:\n  title=\"{title}\" smooth={}\n",
+        if smooth { "yes" } else { "no" }
+    );
+    let mut stops: Vec<_> = gradient
+        .stops
+        .iter()
+        .filter(|stop| stop.position < 1.0)
+        .collect();
+    if stops.is_empty() {
+        stops.push(&gradient.stops[0]);
+    }
+    for stop in stops {
+        let index = ((stop.position * UGR_SIZE as f32).round() as i64).min(UGR_SIZE - 1);
+        let [red, green, blue] = stop.colour.map(|channel| to_byte(channel) as u32);
+        source += &format!("  index={index} color={}\n", red | green << 8 | blue << 16);
+    }
+    source + "opacity:\n  smooth=no index=0 opacity=255\n}\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{builtin, ugr, Interpolation};
+
+    #[test]
+    fn round_trips() {
+        let gradient = builtin("ultra").unwrap();
+        let source = ugr::write(&gradient, "Ultra");
+        assert!(source.starts_with(
+            "Ultra {\ngradient:\n  title=\"Ultra\" smooth=yes\n  index=0 color=6555392\n"
+        ));
+        assert_eq!(ugr::parse(&source), Ok(gradient.clone()));
+        let source = ugr::write(&gradient, "a}b");
+        assert!(source.starts_with("ab {\ngradient:\n  title=\"a}b\" smooth=yes\n"));
+        assert_eq!(ugr::parse(&source), Ok(gradient));
+    }
+
+    #[test]
+    fn wraps_around() {
+        let gradient = ugr::parse(
+            "Two {\ngradient:\n  title=\"Two colours\" smooth=no\n  index=100 color=255 index=300 \
+             color=16711680\nopacity:\n  smooth=no index=0 opacity=255\n}\n",
+        )
+        .unwrap();
+        let stops = &gradient.stops;
+        assert_eq!(stops.len(), 4);
+        // Halfway from blue at 300 round to red at 100
+        assert_eq!(stops[0].position, 0.0);
+        assert_eq!(stops[0].colour, [0.5, 0.0, 0.5]);
+        assert_eq!(stops[1].colour, [1.0, 0.0, 0.0]);
+        assert_eq!(stops[2].colour, [0.0, 0.0, 1.0]);
+        assert_eq!(stops[3].colour, stops[0].colour);
+        assert_eq!(stops[3].interpolation, Interpolation::Linear);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert_eq!(
+            ugr::parse("Empty {\n}\n"),
+            Err(String::from("No gradient: section found"))
+        );
+        assert_eq!(
+            ugr::parse("A {\ngradient:\n  color=255\n}"),
+            Err(String::from("color=255 needs an index before it"))
+        );
+        assert!(ugr::parse("A {\ngradient:\n  index=zero color=255\n}").is_err());
+        assert!(ugr::parse("A {\ngradient:\n  index=0 color=-1\n}").is_err());
+        assert!(ugr::parse("A {\ngradient:\n  title=\"A\n}").is_err());
+        assert!(ugr::parse("A {\ngradient:\n  title=\"A\"\n}").is_err());
+        // Braces in quotes don't end the gradient
+        assert_eq!(
+            ugr::parse("A {\ngradient:\n  title=\"a}b\" color=255\n}"),
+            Err(String::from("color=255 needs an index before it"))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    fixed::{precision_for_scale, Fixed},
+    formula::Formula,
+    hybrid::Hybrid,
+    view::View,
+};
+
+/// Everything needed to come back to a view, saved in the page's URL so it can be reloaded, bookmarked or shared
+#[derive(Clone, Debug, PartialEq)]
+pub struct Params {
+    pub view: View,
+    pub formula: Formula,
+    pub power: f64,
+    /// Definition drawn when `formula` is [`Formula::Hybrid`]
+    pub hybrid: Option<Hybrid>,
+    /// `c` of the Julia set being shown, if it is one
+    pub julia: Option<(f64, f64)>,
+    /// Iteration limit, unless it's chosen automatically
+    pub depth: Option<u32>,
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {key} '{value}'"))
+}
+
+/// Like [`parse_number`], but also rejects `NaN` and infinities
+fn parse_finite(key: &str, value: &str) -> Result<f64, String> {
+    Some(parse_number::<f64>(key, value)?)
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("Invalid {key} '{value}'"))
+}
+
+impl Params {
+    /// Keys and values to save, which still need escaping to go in a URL
+    ///
+    /// The centre is saved exactly, see [`Fixed::to_hex`]
+    pub fn to_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![
+            ("x", self.view.center.0.to_hex()),
+            ("y", self.view.center.1.to_hex()),
+            ("scale", self.view.scale.to_string()),
+        ];
+        if self.view.rotation != 0.0 {
+            pairs.push(("rotation", self.view.rotation.to_string()));
+        }
+        match &self.hybrid {
+            Some(hybrid) if self.formula == Formula::Hybrid => {
+                pairs.push(("hybrid", hybrid.to_string()))
+            }
+            _ => pairs.push(("formula", self.formula.name().to_string())),
+        }
+        pairs.push(("power", self.power.to_string()));
+        if let Some((re, im)) = self.julia {
+            pairs.push(("julia", format!("{re},{im}")));
+        }
+        if let Some(depth) = self.depth {
+            pairs.push(("depth", depth.to_string()));
+        }
+        pairs
+    }
+
+    /// Reads back the output of [`Params::to_pairs`], ignoring any keys it doesn't know about
+    pub fn from_pairs<'a>(
+        pairs: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, String> {
+        let mut params = Self {
+            view: View::default(),
+            formula: Formula::Mandelbrot,
+            power: 2.0,
+            hybrid: None,
+            julia: None,
+            depth: None,
+        };
+        let mut center = (String::from("0"), String::from("0"));
+        for (key, value) in pairs {
+            match key {
+                "x" => center.0 = value.to_string(),
+                "y" => center.1 = value.to_string(),
+                "scale" => params.view.scale = parse_number(key, value)?,
+                "rotation" => params.view.rotation = parse_finite(key, value)?,
+                "formula" => {
+                    params.formula = Formula::from_name(value)
+                        .ok_or_else(|| format!("Unknown formula '{value}'"))?
+                }
+                "hybrid" => {
+                    params.hybrid = Some(Hybrid::parse(value)?);
+                    params.formula = Formula::Hybrid;
+                }
+                "power" => params.power = parse_finite(key, value)?,
+                "julia" => {
+                    let (re, im) = value
+                        .split_once(',')
+                        .ok_or_else(|| format!("Invalid julia '{value}'"))?;
+                    params.julia = Some((parse_finite(key, re)?, parse_finite(key, im)?));
+                }
+                "depth" => params.depth = Some(parse_number(key, value)?),
+                _ => {}
+            }
+        }
+        if !(params.view.scale > 0.0 && params.view.scale.is_finite()) {
+            return Err(format!("Invalid scale '{}'", params.view.scale));
+        }
+        let precision = precision_for_scale(params.view.scale);
+        let parse_fixed = |value: &str| {
+            Fixed::from_hex(value, precision).ok_or_else(|| format!("Invalid centre '{value}'"))
+        };
+        params.view.center = (parse_fixed(&center.0)?, parse_fixed(&center.1)?);
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixed::Fixed, formula::Formula, hybrid::Hybrid, params::Params, view::View};
+
+    fn round_trip(params: &Params) -> Params {
+        let pairs = params.to_pairs();
+        Params::from_pairs(pairs.iter().map(|(key, value)| (*key, value.as_str()))).unwrap()
+    }
+
+    #[test]
+    fn saves_hybrids_with_the_view() {
+        let deep = &Fixed::from_f64(-0.75, 5) + &Fixed::from_f64(2_f64.powi(-100), 5);
+        let params = Params {
+            view: View {
+                center: (deep, Fixed::from_f64(0.1, 5)),
+                scale: 1.0e-25,
+                rotation: 0.5,
+            },
+            formula: Formula::Hybrid,
+            power: 3.0,
+            hybrid: Some(Hybrid::parse("2x Mandelbrot, 1x Burning Ship abs(im)").unwrap()),
+            julia: Some((-0.8, 0.156)),
+            depth: Some(5000),
+        };
+        assert_eq!(round_trip(&params), params);
+        let plain = Params {
+            view: View::default(),
+            formula: Formula::Celtic,
+            power: 2.0,
+            hybrid: None,
+            julia: None,
+            depth: None,
+        };
+        assert_eq!(round_trip(&plain).to_pairs(), plain.to_pairs());
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(Params::from_pairs([("scale", "0")]).is_err());
+        assert!(Params::from_pairs([("formula", "Mandelbulb")]).is_err());
+        assert!(Params::from_pairs([("hybrid", "2x Mandelbulb")]).is_err());
+        assert!(Params::from_pairs([("x", "zz")]).is_err());
+        assert!(Params::from_pairs([("rotation", "NaN")]).is_err());
+        assert!(Params::from_pairs([("julia", "inf,0")]).is_err());
+        assert!(Params::from_pairs([("power", "-inf")]).is_err());
+        assert_eq!(
+            Params::from_pairs([("unknown", "1"), ("power", "4")])
+                .unwrap()
+                .power,
+            4.0
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    complex::{add, mul, norm, scale, Complex},
+    fixed::Fixed,
+};
+
+/// Width of the texture the reference orbit is uploaded as, must match `ORBIT_WIDTH` in `perturbation.frag`
+pub const ORBIT_WIDTH: usize = 1024;
+
+/// Largest size of the cubic term of the series relative to the linear term before it's no longer trusted
+const SERIES_TOLERANCE: f64 = 1.0e-6;
+
+/// Orbit of a single point iterated at full precision, which every other pixel is iterated relative to
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReferenceOrbit {
+    pub center: (Fixed, Fixed),
+    pub depth: u32,
+    /// `c` of the Julia set the orbit belongs to, or `None` for the Mandelbrot set where `c` is the centre
+    pub julia: Option<(f64, f64)>,
+    /// `z_0` to `z_n`, where `z_n` either escaped or `n` is `depth`
+    pub points: Vec<Complex>,
+}
+
+/// Truncated power series in the pixel offset `d` which approximates the delta from the reference after `skip` iterations
+///
+/// Deltas are measured in units of `2^exponent`, so a pixel starts at iteration `skip` with
+/// `w = coefficients[0] * d + coefficients[1] * d^2 + coefficients[2] * d^3`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeriesApproximation {
+    pub skip: usize,
+    pub coefficients: [[f32; 2]; 3],
+    pub exponent: i32,
+}
+
+impl ReferenceOrbit {
+    pub fn compute(center: &(Fixed, Fixed), depth: u32, julia: Option<(f64, f64)>) -> Self {
+        let precision = center.0.precision().max(center.1.precision());
+        let ((mut z_re, mut z_im), (c_re, c_im)) = match julia {
+            Some((re, im)) => (
+                center.clone(),
+                (
+                    Fixed::from_f64(re, precision),
+                    Fixed::from_f64(im, precision),
+                ),
+            ),
+            None => (
+                (Fixed::zero(precision), Fixed::zero(precision)),
+                center.clone(),
+            ),
+        };
+        let mut points = Vec::with_capacity(depth as usize + 1);
+        points.push([z_re.to_f64(), z_im.to_f64()]);
+        for _ in 0..depth {
+            let re_im = &z_re * &z_im;
+            let re = &(&(&z_re * &z_re) - &(&z_im * &z_im)) + &c_re;
+            z_im = &(&re_im + &re_im) + &c_im;
+            z_re = re;
+            let (x, y) = (z_re.to_f64(), z_im.to_f64());
+            points.push([x, y]);
+            if x * x + y * y > 4.0 {
+                break;
+            }
+        }
+        Self {
+            center: center.clone(),
+            depth,
+            julia,
+            points,
+        }
+    }
+
+    /// Flattened texel data padded to a whole number of [`ORBIT_WIDTH`] rows, returned with the number of rows
+    pub fn texture_data(&self) -> (Vec<f32>, usize) {
+        let rows = self.points.len().div_ceil(ORBIT_WIDTH);
+        let mut data = Vec::with_capacity(rows * ORBIT_WIDTH * 2);
+        data.extend(self.points.iter().flatten().map(|value| *value as f32));
+        data.resize(rows * ORBIT_WIDTH * 2, 0.0);
+        (data, rows)
+    }
+
+    /// Finds how many iterations can be skipped for pixels whose offsets `d` from the reference, in units of
+    /// `2^scale_exponent`, are at most `max_delta`
+    pub fn series(&self, scale_exponent: i32, max_delta: f64) -> SeriesApproximation {
+        // With dc = d * 2^e and dz = w * 2^e, w_(n+1) = 2 z_n w_n + 2^e w_n^2 + d, which gives the recurrences
+        // a_(n+1) = 2 z_n a_n + 1, b_(n+1) = 2 z_n b_n + 2^e a_n^2 and c_(n+1) = 2 z_n c_n + 2^(e+1) a_n b_n
+        // Julia sets have no dc, but start from w_0 = d instead, so a_0 = 1 and the + 1 goes
+        let factor = 2_f64.powi(scale_exponent);
+        let (initial, dc) = match self.julia {
+            Some(_) => ([1.0, 0.0], [0.0, 0.0]),
+            None => ([0.0, 0.0], [1.0, 0.0]),
+        };
+        let mut terms = [initial, [0.0; 2], [0.0; 2]];
+        let mut skip = 0;
+        for (n, z) in self.points.iter().enumerate().take(self.points.len() - 1) {
+            let [a, b, c] = terms;
+            let two_z = scale(*z, 2.0);
+            let next = [
+                add(mul(two_z, a), dc),
+                add(mul(two_z, b), scale(mul(a, a), factor)),
+                add(mul(two_z, c), scale(mul(a, b), 2.0 * factor)),
+            ];
+            if !next.iter().flatten().all(|value| value.is_finite())
+                || norm(next[2]) * max_delta * max_delta > SERIES_TOLERANCE * norm(next[0])
+            {
+                break;
+            }
+            terms = next;
+            skip = n + 1;
+        }
+        if skip == 0 {
+            return SeriesApproximation {
+                skip,
+                coefficients: terms.map(|term| [term[0] as f32, term[1] as f32]),
+                exponent: scale_exponent,
+            };
+        }
+        // Move the size of the linear term into the exponent so the coefficients fit in single precision
+        let shift = norm(terms[0]).log2().floor() as i32;
+        let inverse = 2_f64.powi(-shift);
+        SeriesApproximation {
+            skip,
+            coefficients: terms
+                .map(|term| [(term[0] * inverse) as f32, (term[1] * inverse) as f32]),
+            exponent: scale_exponent + shift,
+        }
+    }
+}
+
+/// Picks a pixel to place a new reference at, near the middle of the largest blob of glitched pixels
+///
+/// `iterations` holds the RGBA texels read back from the iteration buffer, where `g` flags glitched pixels
+pub fn find_glitch(iterations: &[f32], width: usize, height: usize) -> Option<(usize, usize)> {
+    let glitched = |index: usize| iterations[index * 4 + 1] != 0.0;
+    let mut visited = vec![false; width * height];
+    let mut largest: Vec<usize> = Vec::new();
+    for start in 0..width * height {
+        if visited[start] || !glitched(start) {
+            continue;
+        }
+        visited[start] = true;
+        let mut blob = Vec::new();
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            blob.push(index);
+            let (x, y) = (index % width, index / width);
+            let neighbours = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ];
+            for neighbour in neighbours.into_iter().flatten() {
+                if !visited[neighbour] && glitched(neighbour) {
+                    visited[neighbour] = true;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        if blob.len() > largest.len() {
+            largest = blob;
+        }
+    }
+    // The centroid might fall outside of the blob, so take the closest pixel inside it instead
+    let count = largest.len() as f64;
+    let centroid_x = largest
+        .iter()
+        .map(|index| (index % width) as f64)
+        .sum::<f64>()
+        / count;
+    let centroid_y = largest
+        .iter()
+        .map(|index| (index / width) as f64)
+        .sum::<f64>()
+        / count;
+    largest
+        .into_iter()
+        .map(|index| (index % width, index / width))
+        .min_by(|a, b| {
+            let distance = |(x, y): &(usize, usize)| {
+                (*x as f64 - centroid_x).powi(2) + (*y as f64 - centroid_y).powi(2)
+            };
+            distance(a).total_cmp(&distance(b))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        complex::{add, mul, scale},
+        fixed::Fixed,
+        perturbation::{find_glitch, ReferenceOrbit},
+    };
+
+    #[test]
+    fn matches_f64_iteration() {
+        let (c_re, c_im) = (-0.75, 0.1);
+        let orbit = ReferenceOrbit::compute(
+            &(Fixed::from_f64(c_re, 3), Fixed::from_f64(c_im, 3)),
+            50,
+            None,
+        );
+        let (mut z_re, mut z_im) = (0.0_f64, 0.0_f64);
+        for point in orbit.points.iter().skip(1) {
+            (z_re, z_im) = (z_re * z_re - z_im * z_im + c_re, 2.0 * z_re * z_im + c_im);
+            assert!((point[0] - z_re).abs() < 1e-12);
+            assert!((point[1] - z_im).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn stops_on_escape() {
+        let orbit = ReferenceOrbit::compute(&(Fixed::from_f64(1.0, 2), Fixed::zero(2)), 100, None);
+        // 0, 1, 2, 5
+        assert_eq!(orbit.points.len(), 4);
+        assert_eq!(orbit.texture_data().1, 1);
+    }
+
+    fn assert_series_matches(orbit: &ReferenceOrbit) {
+        let exponent = -30;
+        let series = orbit.series(exponent, 1.5);
+        assert!(series.skip > 10);
+
+        let d = [1.3, -0.7];
+        let (mut w, dc) = match orbit.julia {
+            Some(_) => (d, [0.0, 0.0]),
+            None => ([0.0, 0.0], d),
+        };
+        for z in orbit.points.iter().take(series.skip) {
+            w = add(
+                add(
+                    mul(scale(*z, 2.0), w),
+                    scale(mul(w, w), 2_f64.powi(exponent)),
+                ),
+                dc,
+            );
+        }
+        let [a, b, c] = series
+            .coefficients
+            .map(|term| [term[0] as f64, term[1] as f64]);
+        let approximation = scale(
+            add(add(mul(a, d), mul(b, mul(d, d))), mul(c, mul(d, mul(d, d)))),
+            2_f64.powi(series.exponent - exponent),
+        );
+        assert!((approximation[0] - w[0]).hypot(approximation[1] - w[1]) < 1e-5 * w[0].hypot(w[1]));
+    }
+
+    #[test]
+    fn series_matches_iteration() {
+        let center = (Fixed::from_f64(-0.1, 3), Fixed::from_f64(0.1, 3));
+        assert_series_matches(&ReferenceOrbit::compute(&center, 1000, None));
+        assert_series_matches(&ReferenceOrbit::compute(&center, 1000, Some((-0.2, 0.6))));
+    }
+
+    #[test]
+    fn glitch_in_largest_blob() {
+        let (width, height) = (8, 6);
+        let mut iterations = vec![0.0; width * height * 4];
+        let mut flag = |x: usize, y: usize| iterations[(y * width + x) * 4 + 1] = 1.0;
+        flag(0, 0);
+        // An L shape whose centroid lies outside of it
+        for y in 1..6 {
+            flag(6, y);
+        }
+        for x in 2..6 {
+            flag(x, 5);
+        }
+        let (x, y) = find_glitch(&iterations, width, height).unwrap();
+        assert!(x == 6 || y == 5);
+        assert_eq!(find_glitch(&[0.0; 16], 2, 2), None);
+    }
+}
@@ -0,0 +1,187 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::matrix::Matrix3;
+
+/// Closest the camera's pitch gets to looking straight up or down, where its orientation breaks down
+const MAX_PITCH: f64 = FRAC_PI_2 - 0.01;
+
+const MIN_DISTANCE: f64 = 0.01;
+const MAX_DISTANCE: f64 = 100.0;
+
+/// Distance from the camera to the screen, in units of half the screen's width, which gives a field of view of
+/// about 53 degrees
+const FOCAL_LENGTH: f64 = 2.0;
+
+/// 3D fractal drawn by ray marching its distance estimate in `raymarch.frag`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// Raises points to a power in spherical coordinates, the power being the one z is raised to in 2D
+    Mandelbulb,
+    /// Folds space into a box and sphere, then scales it by the power
+    Mandelbox,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 2] = [Shape::Mandelbulb, Shape::Mandelbox];
+
+    /// Value of the `shape` uniform in `raymarch.frag`
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Mandelbulb => "Mandelbulb",
+            Shape::Mandelbox => "Mandelbox",
+        }
+    }
+
+    /// Parses a name like `"mandelbulb"`, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|shape| shape.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Power the shape is usually drawn with, which the Mandelbox uses as its scale
+    pub fn default_power(self) -> f64 {
+        match self {
+            Shape::Mandelbulb => 8.0,
+            Shape::Mandelbox => 2.0,
+        }
+    }
+
+    /// Camera which fits the whole shape in view
+    pub fn camera(self) -> Camera {
+        Camera {
+            distance: match self {
+                Shape::Mandelbulb => 3.0,
+                // The Mandelbox with a scale of 2 fills a cube 12 units across
+                Shape::Mandelbox => 16.0,
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Camera orbiting the origin, which it always looks at, with y pointing up
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    /// Angle around the vertical axis in radians, where 0 looks along -z
+    pub yaw: f64,
+    /// Angle above the horizontal plane in radians
+    pub pitch: f64,
+    pub distance: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            yaw: 0.6,
+            pitch: 0.4,
+            distance: 3.0,
+        }
+    }
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f64; 3]) -> [f64; 3] {
+    let length = (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt();
+    a.map(|value| value / length)
+}
+
+impl Camera {
+    pub fn position(&self) -> [f64; 3] {
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+        [
+            self.distance * pitch_cos * yaw_sin,
+            self.distance * pitch_sin,
+            self.distance * pitch_cos * yaw_cos,
+        ]
+    }
+
+    /// Swings the camera around the origin by a distance measured on the screen, where crossing the whole screen is
+    /// half a turn
+    pub fn orbit(&mut self, (x, y): (f64, f64)) {
+        self.yaw = (self.yaw + x * FRAC_PI_2) % (PI * 2.0);
+        self.pitch = (self.pitch + y * FRAC_PI_2).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Multiplies the distance to the origin by `factor`
+    pub fn zoom(&mut self, factor: f64) {
+        self.distance = (self.distance * factor).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    /// Transform from screen space to the direction of the ray through each point, which isn't normalised
+    pub fn ray_transform(&self, aspect: f64) -> Matrix3 {
+        let forward = normalize(self.position().map(|value| -value));
+        let right = normalize(cross(forward, [0.0, 1.0, 0.0]));
+        let up = cross(right, forward);
+        let row = |axis: usize| {
+            [right[axis], up[axis] * aspect, forward[axis] * FOCAL_LENGTH].map(|value| value as f32)
+        };
+        Matrix3([row(0), row(1), row(2)].concat().try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::raymarch::{Camera, Shape, MAX_PITCH};
+
+    fn ray(camera: &Camera, point: (f32, f32)) -> [f64; 3] {
+        let transform = camera.ray_transform(0.5);
+        [0, 3, 6].map(|row| {
+            (transform[row] * point.0 + transform[row + 1] * point.1 + transform[row + 2]) as f64
+        })
+    }
+
+    fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+    }
+
+    #[test]
+    fn looks_at_origin() {
+        let camera = Camera {
+            yaw: 2.0,
+            pitch: -0.7,
+            distance: 5.0,
+        };
+        let position = camera.position();
+        assert!((dot(position, position).sqrt() - 5.0).abs() < 1e-9);
+        let forward = ray(&camera, (0.0, 0.0));
+        let cosine = -dot(forward, position) / dot(forward, forward).sqrt() / 5.0;
+        assert!((cosine - 1.0).abs() < 1e-6);
+        // The right of the screen stays level and the top leans upwards
+        let right = ray(&camera, (1.0, 0.0));
+        assert!((right[1] - forward[1]).abs() < 1e-6);
+        assert!(ray(&camera, (0.0, 1.0))[1] > forward[1]);
+    }
+
+    #[test]
+    fn orbit_stops_short_of_poles() {
+        let mut camera = Camera::default();
+        camera.orbit((0.5, 3.0));
+        assert_eq!(camera.pitch, MAX_PITCH);
+        camera.orbit((0.0, -6.0));
+        assert_eq!(camera.pitch, -MAX_PITCH);
+        camera.zoom(1e-9);
+        assert!(camera.distance > 0.0);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for shape in Shape::ALL {
+            assert_eq!(Shape::from_name(shape.name()), Some(shape));
+        }
+        assert_eq!(Shape::from_name("MANDELBOX"), Some(Shape::Mandelbox));
+        assert_eq!(Shape::from_name("mandelbrot"), None);
+    }
+}
@@ -0,0 +1,91 @@
+use web_sys::{WebGl2RenderingContext, WebGlFramebuffer, WebGlTexture};
+
+use crate::tiles::Tile;
+
+/// Float texture which the escape-time programs render into, before it's coloured onto the canvas
+///
+/// Each texel holds the escape ratio in `r` and the distance estimate in pixels in `a`, or -1 where there isn't one,
+/// with the other channels depending on the program
+#[derive(Clone, Debug)]
+pub struct IterationBuffer {
+    pub framebuffer: WebGlFramebuffer,
+    pub texture: WebGlTexture,
+}
+
+impl IterationBuffer {
+    pub fn new(context: &WebGl2RenderingContext) -> Self {
+        let texture = context.create_texture().unwrap();
+        context.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        for (parameter, value) in [
+            (
+                WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+                WebGl2RenderingContext::NEAREST,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+                WebGl2RenderingContext::NEAREST,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_S,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_T,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            context.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, parameter, value as i32);
+        }
+        let framebuffer = context.create_framebuffer().unwrap();
+        Self {
+            framebuffer,
+            texture,
+        }
+    }
+
+    /// Reallocates the texture, discarding its contents
+    pub fn resize(&self, context: &WebGl2RenderingContext, width: i32, height: i32) {
+        context.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+        context
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+                WebGl2RenderingContext::TEXTURE_2D,
+                0,
+                WebGl2RenderingContext::RGBA32F as i32,
+                width,
+                height,
+                0,
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::FLOAT,
+                None,
+            )
+            .unwrap();
+        context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        context.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(&self.texture),
+            0,
+        );
+        context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    }
+
+    /// Reads back the texels of `[x, y, width, height]` as RGBA, bottom row first
+    pub fn read(&self, context: &WebGl2RenderingContext, [x, y, width, height]: Tile) -> Vec<f32> {
+        let data = js_sys::Float32Array::new_with_length((width * height * 4) as u32);
+        context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        context
+            .read_pixels_with_opt_array_buffer_view(
+                x,
+                y,
+                width,
+                height,
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::FLOAT,
+                Some(&data),
+            )
+            .unwrap();
+        context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        data.to_vec()
+    }
+}
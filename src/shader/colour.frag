@@ -0,0 +1,69 @@
+#version 300 es
+
+precision highp float;
+precision highp sampler2D;
+
+in vec2 vPos;
+
+// Output of one of the escape-time programs
+uniform sampler2D iterations;
+// What the iterations hold, see Colouring in mod.rs
+uniform int colouring;
+
+// Width in pixels of the lines drawn where escape-time programs estimated points to be this close to the set, or 0
+uniform float line_thickness;
+// Canvas pixels per pixel of the iteration buffers, which have distance estimates in buffer pixels
+uniform float buffer_scale;
+// How points which didn't escape are coloured, see Interior in interior.rs, or 0 for flat if shader.frag didn't draw
+// the buffer
+uniform int interior;
+
+const int SHADED = 1;
+const int LYAPUNOV = 2;
+
+const int PERIOD = 1;
+const int MULTIPLIER = 2;
+const int INTERIOR_DISTANCE = 3;
+
+out vec4 outColor;
+
+#include "colour.glsl"
+
+void main() {
+    vec4 value = texture(iterations, vPos * 0.5 + 0.5);
+    // raymarch.frag lights pixels itself
+    if (colouring == SHADED) {
+        outColor = vec4(value.rgb, 1.0);
+        return;
+    }
+    if (colouring == LYAPUNOV) {
+        outColor = lyapunov(value.x);
+        return;
+    }
+    // shader.frag leaves what each interior mode needs for points which didn't escape, see inside() there
+    // Points without a cycle are left flat
+    if (value.x >= 1.0) {
+        if (interior == PERIOD && value.y > 0.0) {
+            outColor = period_colour(value.y);
+            return;
+        }
+        if (interior == MULTIPLIER && value.w >= 0.0) {
+            outColor = multiplier_colour(value.y, value.w);
+            return;
+        }
+        if (interior == INTERIOR_DISTANCE && value.w >= 0.0) {
+            outColor = interior_distance_colour(value.w * buffer_scale);
+            return;
+        }
+    }
+    // Newton's method marks the root each pixel converged to in z
+    if (value.z > 0.0) {
+        outColor = basin(value.z, value.x);
+    } else {
+        outColor = colour(value.x);
+        // Escaped points have a distance estimate in w, or -1 if there isn't one
+        if (line_thickness > 0.0 && value.x < 1.0 && value.w >= 0.0) {
+            outColor.rgb *= clamp(value.w * buffer_scale / line_thickness, 0.0, 1.0);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// Gradient baked by Gradient::bake in palette.rs, one texel high
+uniform sampler2D palette;
+// How escape ratios are spread over the palette, see Mapping in palette.rs
+uniform float palette_offset;
+uniform float palette_scale;
+uniform bool palette_repeat;
+uniform bool palette_reverse;
+
+vec4 colour(float ratio) {
+    float position = ratio * palette_scale + palette_offset;
+    position = palette_repeat ? fract(position) : clamp(position, 0.0, 1.0);
+    if (palette_reverse) position = 1.0 - position;
+    return vec4(texture(palette, vec2(position, 0.5)).rgb, 1.0);
+}
+
+// Fully saturated colour with the given hue, from 0 to 1
+vec3 hue_colour(float hue) {
+    return clamp(abs(mod(hue * 6.0 + vec3(0.0, 4.0, 2.0), 6.0) - 3.0) - 1.0, 0.0, 1.0);
+}
+
+// Colour of a pixel which converged to the root with the given hue, from 0 to 1, darker the longer it took
+vec4 basin(float hue, float ratio) {
+    return vec4(hue_colour(hue) * pow(1.0 - ratio, 4.0), 1.0);
+}
+
+// Colour of a hyperbolic component with a cycle of this period, stepping around the colour wheel by the golden ratio
+// so components with nearby periods look different
+vec4 period_colour(float period) {
+    return vec4(hue_colour(fract(period * 0.618034)) * 0.8, 1.0);
+}
+
+// Colour of an interior point by its cycle's multiplier, with the hue going round with its angle from 0 to 1, dark at
+// the centre of each component and bright at its edge where the magnitude reaches 1
+vec4 multiplier_colour(float angle, float magnitude) {
+    return vec4(hue_colour(angle) * clamp(magnitude, 0.0, 1.0), 1.0);
+}
+
+// Pixels over which the glow along the inside of the edge fades
+const float INTERIOR_GLOW = 8.0;
+
+// Colour of an interior point this many pixels from the edge of the set, glowing white at the edge and fading to
+// black further in
+vec4 interior_distance_colour(float distance) {
+    return vec4(vec3(exp(-distance / INTERIOR_GLOW)), 1.0);
+}
+
+// Colour of a Lyapunov exponent, gold where orbits settle down and blue where they're chaotic, both fading to
+// black where the exponent is close to 0
+vec4 lyapunov(float exponent) {
+    if (exponent < 0.0) {
+        return vec4(vec3(1.0, 0.8, 0.2) * (1.0 - exp(exponent * 2.0)), 1.0);
+    }
+    return vec4(vec3(0.2, 0.45, 1.0) * (1.0 - exp(-exponent * 4.0)), 1.0);
+}
@@ -0,0 +1,67 @@
+// Complex arithmetic for shader.frag, custom formulas and Newton's method, where complex numbers are vec2s of the real and imaginary parts
+
+vec2 cmul(vec2 a, vec2 b) {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+vec2 cdiv(vec2 a, vec2 b) {
+    return vec2(a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / dot(b, b);
+}
+
+vec2 cconj(vec2 a) {
+    return vec2(a.x, -a.y);
+}
+
+vec2 cexp(vec2 a) {
+    return exp(a.x) * vec2(cos(a.y), sin(a.y));
+}
+
+vec2 clog(vec2 a) {
+    return vec2(log(length(a)), atan(a.y, a.x));
+}
+
+vec2 csqrt(vec2 a) {
+    float angle = atan(a.y, a.x) / 2.0;
+    return sqrt(length(a)) * vec2(cos(angle), sin(angle));
+}
+
+vec2 csin(vec2 a) {
+    return vec2(sin(a.x) * cosh(a.y), cos(a.x) * sinh(a.y));
+}
+
+vec2 ccos(vec2 a) {
+    return vec2(cos(a.x) * cosh(a.y), -sin(a.x) * sinh(a.y));
+}
+
+vec2 ctan(vec2 a) {
+    return cdiv(csin(a), ccos(a));
+}
+
+vec2 csinh(vec2 a) {
+    return vec2(sinh(a.x) * cos(a.y), cosh(a.x) * sin(a.y));
+}
+
+vec2 ccosh(vec2 a) {
+    return vec2(cosh(a.x) * cos(a.y), sinh(a.x) * sin(a.y));
+}
+
+vec2 ctanh(vec2 a) {
+    return cdiv(csinh(a), ccosh(a));
+}
+
+vec2 cpowi(vec2 a, int n) {
+    vec2 result = vec2(1.0, 0.0);
+    for (int i = 0; i < n; i++) result = cmul(result, a);
+    return result;
+}
+
+float rpowi(float a, int n) {
+    float result = 1.0;
+    for (int i = 0; i < n; i++) result *= a;
+    return result;
+}
+
+vec2 cpow(vec2 a, vec2 b) {
+    if (a == vec2(0.0)) return vec2(0.0);
+    return cexp(cmul(b, clog(a)));
+}
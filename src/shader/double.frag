@@ -0,0 +1,91 @@
+#version 300 es
+
+precision highp float;
+
+in vec2 vPos;
+
+uniform int depth;
+uniform mat3 screen_transform;
+// Centre of the view as (re hi, re lo, im hi, im lo)
+uniform vec4 center;
+// Size of one unit of screen_transform as (hi, lo)
+uniform vec2 scale;
+// When set, z starts at the pixel and c is added each iteration instead of the pixel
+uniform bool julia;
+// c is stored like center
+uniform vec4 c;
+
+// Escape ratio in x and the distance estimate in pixels in w, or -1 if there isn't one
+out vec4 outValue;
+
+#include "escape.glsl"
+
+// Double-single arithmetic: each value is the unevaluated sum of a high and a low float
+
+vec2 ds_add(vec2 a, vec2 b) {
+    float sum = a.x + b.x;
+    float v = sum - a.x;
+    float error = (a.x - (sum - v)) + (b.x - v) + a.y + b.y;
+    float hi = sum + error;
+    return vec2(hi, error - (hi - sum));
+}
+
+vec2 ds_split(float a) {
+    float t = a * 4097.0;
+    float hi = t - (t - a);
+    return vec2(hi, a - hi);
+}
+
+vec2 ds_mul(vec2 a, vec2 b) {
+    float product = a.x * b.x;
+    vec2 a_split = ds_split(a.x);
+    vec2 b_split = ds_split(b.x);
+    float error = ((a_split.x * b_split.x - product) + a_split.x * b_split.y + a_split.y * b_split.x)
+        + a_split.y * b_split.y;
+    error += a.x * b.y + a.y * b.x;
+    float hi = product + error;
+    return vec2(hi, error - (hi - product));
+}
+
+// Complex numbers are stored as (re hi, re lo, im hi, im lo)
+vec4 square(vec4 im) {
+    vec2 re_im = ds_mul(im.xy, im.zw);
+    return vec4(
+        ds_add(ds_mul(im.xy, im.xy), -ds_mul(im.zw, im.zw)),
+        2.0 * re_im
+    );
+}
+
+vec2 mul(vec2 a, vec2 b) {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// Escape ratio in x and the distance estimate in y
+vec2 pixel(vec4 pos) {
+    vec4 z = julia ? pos : vec4(0.0, 0.0, 0.0, 0.0);
+    vec4 offset = julia ? c : pos;
+    // The derivative only needs single precision, like dz in shader.frag
+    vec2 dz = julia ? vec2(1.0, 0.0) : vec2(0.0);
+    vec2 dc = julia ? vec2(0.0) : vec2(1.0, 0.0);
+    float radius = smooth_colouring || distance_estimation ? SMOOTH_BAILOUT : 2.0;
+    for (int i = 0; i < depth; i++) {
+        if (distance_estimation) dz = 2.0 * mul(z.xz, dz) + dc;
+        z = square(z);
+        z = vec4(ds_add(z.xy, offset.xy), ds_add(z.zw, offset.zw));
+        if (z.x * z.x + z.z * z.z > radius * radius) {
+            return vec2(escape_ratio(i, z.xz, radius, 2.0), distance_estimate(z.xz, dz));
+        }
+    }
+    return vec2(1.0, -1.0);
+}
+
+void main() {
+    vec2 offset = (vec3(vPos.x, vPos.y, 1.0) * screen_transform).xy;
+    // Size of a pixel in the plane, to measure distances in pixels
+    float pixel_size = length(dFdx(offset)) * scale.x;
+    vec2 result = pixel(vec4(
+        ds_add(center.xy, ds_mul(scale, vec2(offset.x, 0.0))),
+        ds_add(center.zw, ds_mul(scale, vec2(offset.y, 0.0)))
+    ));
+    outValue = vec4(result.x, 0.0, 0.0, result.y < 0.0 ? -1.0 : result.y / pixel_size);
+}
@@ -0,0 +1,27 @@
+// Needs `depth` declared before it's included
+
+// Radius used instead of the escape radius for smooth colouring, must match SMOOTH_BAILOUT in multibrot.rs
+const float SMOOTH_BAILOUT = 256.0;
+
+// When set, escapes are measured with a continuous iteration count rather than whole iterations
+uniform bool smooth_colouring;
+
+// Escape ratio of an orbit which first got beyond radius on iteration i, reaching z, where each iteration raises z
+// to power
+float escape_ratio(int i, vec2 z, float radius, float power) {
+    if (!smooth_colouring) return float(i) / float(depth);
+    // Goes from i + 1 down to i as |z| goes from radius to radius^power, so it's continuous between bands
+    float count = float(i) + 1.0 - log(log(length(z)) / log(radius)) / log(power);
+    return max(count, 0.0) / float(depth);
+}
+
+// When set, the escape-time programs track the derivative of z for distance_estimate
+uniform bool distance_estimation;
+
+// Distance from an escaped point to the set in the units of the plane, given z as it escaped and its derivative dz
+// with respect to the point, or -1 if there's no estimate
+float distance_estimate(vec2 z, vec2 dz) {
+    if (!distance_estimation) return -1.0;
+    float r = length(z);
+    return 0.5 * r * log(r) / length(dz);
+}
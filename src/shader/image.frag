@@ -0,0 +1,15 @@
+#version 300 es
+
+precision highp float;
+precision highp sampler2D;
+
+in vec2 vPos;
+
+// RGBA image made on the CPU, top row first
+uniform sampler2D image;
+
+out vec4 outColor;
+
+void main() {
+    outColor = texture(image, vec2(vPos.x, -vPos.y) * 0.5 + 0.5);
+}
@@ -0,0 +1,41 @@
+#version 300 es
+
+precision highp float;
+
+in vec2 vPos;
+
+// Must match MAX_SEQUENCE in lyapunov.rs
+const int MAX_SEQUENCE = 32;
+
+uniform int depth;
+// Transform from screen space to the (a, b) parameter plane
+uniform mat3 transform;
+uniform int sequence_length;
+// 1 for each B in the sequence, which takes the rate from b rather than a
+uniform int sequence[MAX_SEQUENCE];
+
+// Lyapunov exponent in x, coloured by colour.frag
+out vec4 outValue;
+
+float exponent(vec2 rates) {
+    // Iterations to let x settle before measuring how quickly nearby orbits separate
+    int warmup = depth / 4;
+    float x = 0.5;
+    float total = 0.0;
+    int n = 0;
+    for (int i = 0; i < warmup + depth; i++) {
+        float rate = sequence[n] == 1 ? rates.y : rates.x;
+        n = n + 1 == sequence_length ? 0 : n + 1;
+        if (i >= warmup) {
+            total += log(max(abs(rate * (1.0 - 2.0 * x)), 1.0e-30));
+        }
+        x = rate * x * (1.0 - x);
+        // Rates outside 0 to 4 send x off to infinity, which is as chaotic as it gets
+        if (abs(x) > 1.0e6) return 1.0e6;
+    }
+    return total / float(depth);
+}
+
+void main() {
+    outValue = vec4(exponent((vec3(vPos.x, vPos.y, 1.0) * transform).xy), 0.0, 0.0, 1.0);
+}
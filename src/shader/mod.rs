@@ -1,9 +1,301 @@
-use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader, WebGlUniformLocation};
+use buffer::IterationBuffer;
+use web_sys::{
+    WebGl2RenderingContext, WebGlProgram, WebGlShader, WebGlTexture, WebGlUniformLocation,
+};
+
+use crate::{
+    formula::Formula,
+    hybrid::Hybrid,
+    interior::Interior,
+    lyapunov::MAX_SEQUENCE,
+    newton::{Polynomial, MAX_DEGREE},
+    palette::{Palette, PALETTE_SIZE},
+    perturbation::{ReferenceOrbit, SeriesApproximation, ORBIT_WIDTH},
+    raymarch::Shape,
+    tiles::{split, Tile},
+};
+
+mod buffer;
+
+/// Attribute location of the full-screen quad, bound to the same slot in every program so they can share a VAO
+const POSITION_LOCATION: u32 = 0;
+
+/// Texture unit holding the reference orbit for `perturbation.frag`
+const ORBIT_TEXTURE_UNIT: u32 = 0;
+
+/// Texture unit holding the front [`IterationBuffer`]
+const ITERATIONS_TEXTURE_UNIT: u32 = 1;
+
+/// Texture unit holding the image given to [`present_image`]
+const IMAGE_TEXTURE_UNIT: u32 = 2;
+
+/// Texture unit holding the palette given to [`set_palette`]
+const PALETTE_TEXTURE_UNIT: u32 = 3;
+
+/// Snippets which can be pulled into a shader with an `#include "name"` line
+const INCLUDES: [(&str, &str); 4] = [
+    ("colour.glsl", include_str!("colour.glsl")),
+    ("complex.glsl", include_str!("complex.glsl")),
+    ("escape.glsl", include_str!("escape.glsl")),
+    // Only filled in for custom formulas, see [`compile_custom`]
+    ("formula.glsl", ""),
+];
+
+/// Views with a smaller scale than this can't be resolved with single precision floats
+const SINGLE_PRECISION_LIMIT: f64 = 1.0e-4;
+
+/// Views with a smaller scale than this can't be resolved with double-single floats, which carry about 48 bits
+const DOUBLE_PRECISION_LIMIT: f64 = 1.0e-13;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    Single,
+    Double,
+    Perturbation,
+}
+
+impl Precision {
+    pub fn for_scale(scale: f64) -> Self {
+        if scale < DOUBLE_PRECISION_LIMIT {
+            Self::Perturbation
+        } else if scale < SINGLE_PRECISION_LIMIT {
+            Self::Double
+        } else {
+            Self::Single
+        }
+    }
+}
+
+/// Splits an [`f64`] into a pair of [`f32`]s whose sum approximates it, as used by `double.frag`
+fn split_f64(value: f64) -> [f32; 2] {
+    let hi = value as f32;
+    [hi, (value - hi as f64) as f32]
+}
+
+/// Uniforms which switch an escape-time program between the Mandelbrot set and a Julia set
+#[derive(Clone, Debug)]
+struct JuliaUniforms {
+    julia_uniform_location: WebGlUniformLocation,
+    c_uniform_location: WebGlUniformLocation,
+}
+
+impl JuliaUniforms {
+    fn new(context: &WebGl2RenderingContext, program: &WebGlProgram) -> Self {
+        Self {
+            julia_uniform_location: context.get_uniform_location(program, "julia").unwrap(),
+            c_uniform_location: context.get_uniform_location(program, "c").unwrap(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SingleProgram {
+    program: WebGlProgram,
+    depth_uniform_location: WebGlUniformLocation,
+    julia: JuliaUniforms,
+    transform_uniform_location: WebGlUniformLocation,
+    power_uniform_location: WebGlUniformLocation,
+    escape_radius_uniform_location: WebGlUniformLocation,
+    /// Custom formulas and hybrids don't use `formula`, so it's optimised out
+    formula_uniform_location: Option<WebGlUniformLocation>,
+    smooth_uniform_location: WebGlUniformLocation,
+    /// Custom formulas don't estimate distances, so it's optimised out of them too
+    distance_uniform_location: Option<WebGlUniformLocation>,
+    /// Only the built-in formulas look for cycles, so it's optimised out of custom formulas and hybrids
+    interior_uniform_location: Option<WebGlUniformLocation>,
+}
+
+impl SingleProgram {
+    fn new(context: &WebGl2RenderingContext, program: WebGlProgram) -> Self {
+        Self {
+            depth_uniform_location: context.get_uniform_location(&program, "depth").unwrap(),
+            julia: JuliaUniforms::new(context, &program),
+            transform_uniform_location: context
+                .get_uniform_location(&program, "transform")
+                .unwrap(),
+            power_uniform_location: context.get_uniform_location(&program, "power").unwrap(),
+            escape_radius_uniform_location: context
+                .get_uniform_location(&program, "escape_radius")
+                .unwrap(),
+            formula_uniform_location: context.get_uniform_location(&program, "formula"),
+            smooth_uniform_location: context
+                .get_uniform_location(&program, "smooth_colouring")
+                .unwrap(),
+            distance_uniform_location: context
+                .get_uniform_location(&program, "distance_estimation"),
+            interior_uniform_location: context.get_uniform_location(&program, "interior"),
+            program,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DoubleProgram {
+    program: WebGlProgram,
+    depth_uniform_location: WebGlUniformLocation,
+    julia: JuliaUniforms,
+    screen_transform_uniform_location: WebGlUniformLocation,
+    center_uniform_location: WebGlUniformLocation,
+    scale_uniform_location: WebGlUniformLocation,
+    smooth_uniform_location: WebGlUniformLocation,
+    distance_uniform_location: WebGlUniformLocation,
+}
+
+#[derive(Clone, Debug)]
+struct PerturbationProgram {
+    program: WebGlProgram,
+    depth_uniform_location: WebGlUniformLocation,
+    julia: JuliaUniforms,
+    delta_transform_uniform_location: WebGlUniformLocation,
+    scale_exponent_uniform_location: WebGlUniformLocation,
+    orbit_length_uniform_location: WebGlUniformLocation,
+    reference_uniform_location: WebGlUniformLocation,
+    skip_uniform_location: WebGlUniformLocation,
+    series_uniform_location: WebGlUniformLocation,
+    series_exponent_uniform_location: WebGlUniformLocation,
+    correcting_uniform_location: WebGlUniformLocation,
+    smooth_uniform_location: WebGlUniformLocation,
+    orbit_texture: WebGlTexture,
+}
+
+#[derive(Clone, Debug)]
+struct NewtonProgram {
+    program: WebGlProgram,
+    depth_uniform_location: WebGlUniformLocation,
+    transform_uniform_location: WebGlUniformLocation,
+    degree_uniform_location: WebGlUniformLocation,
+    coefficients_uniform_location: WebGlUniformLocation,
+    roots_uniform_location: WebGlUniformLocation,
+}
+
+#[derive(Clone, Debug)]
+struct RaymarchProgram {
+    program: WebGlProgram,
+    eye_uniform_location: WebGlUniformLocation,
+    transform_uniform_location: WebGlUniformLocation,
+    shape_uniform_location: WebGlUniformLocation,
+    power_uniform_location: WebGlUniformLocation,
+}
+
+#[derive(Clone, Debug)]
+struct LyapunovProgram {
+    program: WebGlProgram,
+    depth_uniform_location: WebGlUniformLocation,
+    transform_uniform_location: WebGlUniformLocation,
+    sequence_length_uniform_location: WebGlUniformLocation,
+    sequence_uniform_location: WebGlUniformLocation,
+}
+
+/// Uniforms of `colour.frag` which spread escape ratios over the palette, see [`crate::palette::Mapping`]
+#[derive(Clone, Debug)]
+struct PaletteUniforms {
+    offset_uniform_location: WebGlUniformLocation,
+    scale_uniform_location: WebGlUniformLocation,
+    repeat_uniform_location: WebGlUniformLocation,
+    reverse_uniform_location: WebGlUniformLocation,
+}
+
+/// What the iteration buffers hold, which decides how `colour.frag` colours them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Colouring {
+    /// Escape ratios, or the roots converged to by Newton's method
+    Escape,
+    /// Lit colours from `raymarch.frag`
+    Shaded,
+    /// Exponents from `lyapunov.frag`
+    Lyapunov,
+}
+
+/// Pass set up by one of the `draw` functions, which [`draw_tiles`] draws a few tiles at a time
+#[derive(Clone, Debug)]
+struct PendingPass {
+    program: WebGlProgram,
+    /// Region of the buffers drawn into, see [`set_region`]
+    region: Tile,
+    tiles: Vec<Tile>,
+    next: usize,
+}
 
 #[derive(Clone, Debug)]
 pub struct CanvasState {
     vertex_count: i32,
-    transform_uniform_location: WebGlUniformLocation,
+    vert_shader: WebGlShader,
+    single: SingleProgram,
+    /// `shader.frag` with the last formula given to [`compile_custom`]
+    custom: Option<SingleProgram>,
+    /// `shader.frag` with the last hybrid given to [`compile_hybrid`]
+    hybrid: Option<SingleProgram>,
+    double: DoubleProgram,
+    perturbation: PerturbationProgram,
+    newton: NewtonProgram,
+    lyapunov: LyapunovProgram,
+    raymarch: RaymarchProgram,
+    colour: WebGlProgram,
+    colouring_uniform_location: WebGlUniformLocation,
+    line_thickness_uniform_location: WebGlUniformLocation,
+    buffer_scale_uniform_location: WebGlUniformLocation,
+    interior_uniform_location: WebGlUniformLocation,
+    palette: PaletteUniforms,
+    palette_texture: WebGlTexture,
+    /// What the buffer being presented holds
+    colouring: Colouring,
+    /// How points which didn't escape are coloured in the buffer being presented, which is flat unless `shader.frag`
+    /// drew it
+    interior_colouring: Interior,
+    /// Shows images made on the CPU, see [`present_image`]
+    image: WebGlProgram,
+    image_texture: WebGlTexture,
+    /// Each pass renders into the back buffer and then swaps it with the front, so it can read the previous pass
+    buffers: [IterationBuffer; 2],
+    front: usize,
+    /// Size of the iteration buffers, which is the canvas size scaled by `resolution`
+    width: i32,
+    height: i32,
+    canvas_width: i32,
+    canvas_height: i32,
+    resolution: f64,
+    /// Iteration limit last given to [`set_depth`]
+    depth: u32,
+    /// Whether escapes are measured with a continuous iteration count, see [`set_smooth`]
+    smooth: bool,
+    /// Width of the lines drawn along the boundary in pixels, see [`set_line_thickness`]
+    line_thickness: f64,
+    /// Interior mode last given to [`set_interior`]
+    interior: Interior,
+    /// Formula last given to [`set_formula`]
+    formula: Formula,
+    /// Tile size last given to [`set_tile_size`]
+    tile_size: i32,
+    /// Part of the buffers later passes draw into as `[x, y, width, height]`, in fractions of their size
+    region: [f64; 4],
+    pass: Option<PendingPass>,
+}
+
+impl CanvasState {
+    /// Variants of `shader.frag` compiled so far, see [`compile_variant`]
+    fn variants(&self) -> impl Iterator<Item = &SingleProgram> {
+        [self.custom.as_ref(), self.hybrid.as_ref()]
+            .into_iter()
+            .flatten()
+    }
+}
+
+/// Replaces `#include "name"` lines with the matching snippet from [`INCLUDES`]
+fn preprocess(source: &str) -> String {
+    source
+        .lines()
+        .map(|line| {
+            line.trim()
+                .strip_prefix("#include ")
+                .and_then(|name| {
+                    INCLUDES
+                        .iter()
+                        .find(|(include, _)| *include == name.trim_matches('"'))
+                })
+                .map_or(line, |(_, snippet)| snippet)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn compile_shader(
@@ -14,7 +306,7 @@ fn compile_shader(
     let shader = context
         .create_shader(shader_type)
         .ok_or_else(|| String::from("Unable to create shader object"))?;
-    context.shader_source(&shader, source);
+    context.shader_source(&shader, &preprocess(source));
     context.compile_shader(&shader);
 
     if context
@@ -34,53 +326,71 @@ fn link_program(
     context: &WebGl2RenderingContext,
     vert_shader: &WebGlShader,
     frag_shader: &WebGlShader,
-) -> WebGlProgram {
+) -> Result<WebGlProgram, String> {
     let program = context.create_program().unwrap();
 
     context.attach_shader(&program, vert_shader);
     context.attach_shader(&program, frag_shader);
+    context.bind_attrib_location(&program, POSITION_LOCATION, "position");
     context.link_program(&program);
 
     if context
         .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
         .as_bool()
-        .unwrap()
+        .unwrap_or(false)
     {
-        program
+        Ok(program)
     } else {
-        panic!(
-            "{}",
-            context
-                .get_program_info_log(&program)
-                .unwrap_or_else(|| String::from("Unknown error creating program object"))
-        )
+        Err(context
+            .get_program_info_log(&program)
+            .unwrap_or_else(|| String::from("Unknown error creating program object")))
     }
 }
 
-pub fn init_shaders(context: &WebGl2RenderingContext, depth: u16) -> CanvasState {
+fn build_program(
+    context: &WebGl2RenderingContext,
+    vert_shader: &WebGlShader,
+    frag_source: &str,
+) -> WebGlProgram {
+    let frag_shader = compile_shader(
+        context,
+        WebGl2RenderingContext::FRAGMENT_SHADER,
+        frag_source,
+    )
+    .unwrap();
+    let program = link_program(context, vert_shader, &frag_shader).unwrap();
+    context.use_program(Some(&program));
+    program
+}
+
+/// Builds every program, failing if the device can't render into the float textures the iteration buffers use
+pub fn init_shaders(context: &WebGl2RenderingContext) -> Result<CanvasState, String> {
     console_error_panic_hook::set_once();
 
+    // Needed to render into float textures
+    if context
+        .get_extension("EXT_color_buffer_float")
+        .ok()
+        .flatten()
+        .is_none()
+    {
+        return Err(String::from(
+            "EXT_color_buffer_float isn't supported, so this device can't render into float textures",
+        ));
+    }
+
     let vert_shader = compile_shader(
         context,
         WebGl2RenderingContext::VERTEX_SHADER,
         include_str!("shader.vert"),
     )
     .unwrap();
-    let frag_shader = compile_shader(
-        context,
-        WebGl2RenderingContext::FRAGMENT_SHADER,
-        include_str!("shader.frag"),
-    )
-    .unwrap();
-    let program = link_program(&context, &vert_shader, &frag_shader);
-    context.use_program(Some(&program));
 
     let vertices: [f32; 18] = [
         -1.0, -1.0, 0.0, 1.0, -1.0, 0.0, -1.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0, -1.0, 0.0, -1.0, 1.0,
         0.0,
     ];
 
-    let position_attribute_location = context.get_attrib_location(&program, "position");
     let buffer = context.create_buffer().unwrap();
     context.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
 
@@ -106,35 +416,984 @@ pub fn init_shaders(context: &WebGl2RenderingContext, depth: u16) -> CanvasState
     context.bind_vertex_array(Some(&vao));
 
     context.vertex_attrib_pointer_with_i32(
-        position_attribute_location as u32,
+        POSITION_LOCATION,
         3,
         WebGl2RenderingContext::FLOAT,
         false,
         0,
         0,
     );
-    context.enable_vertex_attrib_array(position_attribute_location as u32);
+    context.enable_vertex_attrib_array(POSITION_LOCATION);
 
     context.bind_vertex_array(Some(&vao));
 
-    let depth_uniform_location = context.get_uniform_location(&program, "depth").unwrap();
-    context.uniform1i(Some(&depth_uniform_location), depth as i32);
+    let single = SingleProgram::new(
+        context,
+        build_program(context, &vert_shader, include_str!("shader.frag")),
+    );
+
+    let program = build_program(context, &vert_shader, include_str!("double.frag"));
+    let double = DoubleProgram {
+        depth_uniform_location: context.get_uniform_location(&program, "depth").unwrap(),
+        julia: JuliaUniforms::new(context, &program),
+        screen_transform_uniform_location: context
+            .get_uniform_location(&program, "screen_transform")
+            .unwrap(),
+        center_uniform_location: context.get_uniform_location(&program, "center").unwrap(),
+        scale_uniform_location: context.get_uniform_location(&program, "scale").unwrap(),
+        smooth_uniform_location: context
+            .get_uniform_location(&program, "smooth_colouring")
+            .unwrap(),
+        distance_uniform_location: context
+            .get_uniform_location(&program, "distance_estimation")
+            .unwrap(),
+        program,
+    };
+
+    let program = build_program(context, &vert_shader, include_str!("perturbation.frag"));
+    let orbit_uniform_location = context.get_uniform_location(&program, "orbit").unwrap();
+    context.uniform1i(Some(&orbit_uniform_location), ORBIT_TEXTURE_UNIT as i32);
+    let previous_uniform_location = context.get_uniform_location(&program, "previous").unwrap();
+    context.uniform1i(
+        Some(&previous_uniform_location),
+        ITERATIONS_TEXTURE_UNIT as i32,
+    );
+    let orbit_texture = context.create_texture().unwrap();
+    context.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&orbit_texture));
+    for parameter in [
+        WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+        WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+    ] {
+        context.tex_parameteri(
+            WebGl2RenderingContext::TEXTURE_2D,
+            parameter,
+            WebGl2RenderingContext::NEAREST as i32,
+        );
+    }
+    let perturbation = PerturbationProgram {
+        depth_uniform_location: context.get_uniform_location(&program, "depth").unwrap(),
+        julia: JuliaUniforms::new(context, &program),
+        delta_transform_uniform_location: context
+            .get_uniform_location(&program, "delta_transform")
+            .unwrap(),
+        scale_exponent_uniform_location: context
+            .get_uniform_location(&program, "scale_exponent")
+            .unwrap(),
+        orbit_length_uniform_location: context
+            .get_uniform_location(&program, "orbit_length")
+            .unwrap(),
+        reference_uniform_location: context.get_uniform_location(&program, "reference").unwrap(),
+        skip_uniform_location: context.get_uniform_location(&program, "skip").unwrap(),
+        series_uniform_location: context.get_uniform_location(&program, "series").unwrap(),
+        series_exponent_uniform_location: context
+            .get_uniform_location(&program, "series_exponent")
+            .unwrap(),
+        correcting_uniform_location: context
+            .get_uniform_location(&program, "correcting")
+            .unwrap(),
+        smooth_uniform_location: context
+            .get_uniform_location(&program, "smooth_colouring")
+            .unwrap(),
+        orbit_texture,
+        program,
+    };
+
+    let program = build_program(context, &vert_shader, include_str!("newton.frag"));
+    let newton = NewtonProgram {
+        depth_uniform_location: context.get_uniform_location(&program, "depth").unwrap(),
+        transform_uniform_location: context.get_uniform_location(&program, "transform").unwrap(),
+        degree_uniform_location: context.get_uniform_location(&program, "degree").unwrap(),
+        coefficients_uniform_location: context
+            .get_uniform_location(&program, "coefficients")
+            .unwrap(),
+        roots_uniform_location: context.get_uniform_location(&program, "roots").unwrap(),
+        program,
+    };
+
+    let program = build_program(context, &vert_shader, include_str!("lyapunov.frag"));
+    let lyapunov = LyapunovProgram {
+        depth_uniform_location: context.get_uniform_location(&program, "depth").unwrap(),
+        transform_uniform_location: context.get_uniform_location(&program, "transform").unwrap(),
+        sequence_length_uniform_location: context
+            .get_uniform_location(&program, "sequence_length")
+            .unwrap(),
+        sequence_uniform_location: context.get_uniform_location(&program, "sequence").unwrap(),
+        program,
+    };
+
+    let program = build_program(context, &vert_shader, include_str!("raymarch.frag"));
+    let raymarch = RaymarchProgram {
+        eye_uniform_location: context.get_uniform_location(&program, "eye").unwrap(),
+        transform_uniform_location: context.get_uniform_location(&program, "transform").unwrap(),
+        shape_uniform_location: context.get_uniform_location(&program, "shape").unwrap(),
+        power_uniform_location: context.get_uniform_location(&program, "power").unwrap(),
+        program,
+    };
 
-    let transform_uniform_location = context.get_uniform_location(&program, "transform").unwrap();
+    let colour = link_program(
+        context,
+        &vert_shader,
+        &compile_shader(
+            context,
+            WebGl2RenderingContext::FRAGMENT_SHADER,
+            include_str!("colour.frag"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    context.use_program(Some(&colour));
+    let iterations_uniform_location = context.get_uniform_location(&colour, "iterations").unwrap();
+    context.uniform1i(
+        Some(&iterations_uniform_location),
+        ITERATIONS_TEXTURE_UNIT as i32,
+    );
+    let colouring_uniform_location = context.get_uniform_location(&colour, "colouring").unwrap();
+    let line_thickness_uniform_location = context
+        .get_uniform_location(&colour, "line_thickness")
+        .unwrap();
+    let buffer_scale_uniform_location = context
+        .get_uniform_location(&colour, "buffer_scale")
+        .unwrap();
+    let interior_uniform_location = context.get_uniform_location(&colour, "interior").unwrap();
+    let palette_uniform_location = context.get_uniform_location(&colour, "palette").unwrap();
+    context.uniform1i(Some(&palette_uniform_location), PALETTE_TEXTURE_UNIT as i32);
+    let palette = PaletteUniforms {
+        offset_uniform_location: context
+            .get_uniform_location(&colour, "palette_offset")
+            .unwrap(),
+        scale_uniform_location: context
+            .get_uniform_location(&colour, "palette_scale")
+            .unwrap(),
+        repeat_uniform_location: context
+            .get_uniform_location(&colour, "palette_repeat")
+            .unwrap(),
+        reverse_uniform_location: context
+            .get_uniform_location(&colour, "palette_reverse")
+            .unwrap(),
+    };
+
+    let image = build_program(context, &vert_shader, include_str!("image.frag"));
+    let image_uniform_location = context.get_uniform_location(&image, "image").unwrap();
+    context.uniform1i(Some(&image_uniform_location), IMAGE_TEXTURE_UNIT as i32);
+    let image_texture = context.create_texture().unwrap();
+    let palette_texture = context.create_texture().unwrap();
+    for (unit, texture) in [
+        (IMAGE_TEXTURE_UNIT, &image_texture),
+        (PALETTE_TEXTURE_UNIT, &palette_texture),
+    ] {
+        context.active_texture(WebGl2RenderingContext::TEXTURE0 + unit);
+        context.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture));
+        for (parameter, value) in [
+            (
+                WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+                WebGl2RenderingContext::NEAREST,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+                WebGl2RenderingContext::NEAREST,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_S,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_T,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            context.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, parameter, value as i32);
+        }
+    }
 
-    CanvasState {
-        transform_uniform_location,
+    Ok(CanvasState {
         vertex_count: (vertices.len() / 3) as i32,
+        vert_shader,
+        single,
+        custom: None,
+        hybrid: None,
+        double,
+        perturbation,
+        newton,
+        lyapunov,
+        raymarch,
+        colour,
+        colouring_uniform_location,
+        line_thickness_uniform_location,
+        buffer_scale_uniform_location,
+        interior_uniform_location,
+        palette,
+        palette_texture,
+        colouring: Colouring::Escape,
+        interior_colouring: Interior::Flat,
+        image,
+        image_texture,
+        buffers: [IterationBuffer::new(context), IterationBuffer::new(context)],
+        front: 0,
+        width: 0,
+        height: 0,
+        canvas_width: 0,
+        canvas_height: 0,
+        resolution: 1.0,
+        depth: 0,
+        smooth: false,
+        line_thickness: 0.0,
+        interior: Interior::Flat,
+        formula: Formula::Mandelbrot,
+        tile_size: i32::MAX,
+        region: [0.0, 0.0, 1.0, 1.0],
+        pass: None,
+    })
+}
+
+/// Sets the iteration limit of every escape-time program
+pub fn set_depth(context: &WebGl2RenderingContext, options: &mut CanvasState, depth: u32) {
+    if options.depth == depth {
+        return;
+    }
+    let variants = options
+        .variants()
+        .map(|variant| (&variant.program, &variant.depth_uniform_location));
+    for (program, location) in [
+        (
+            &options.single.program,
+            &options.single.depth_uniform_location,
+        ),
+        (
+            &options.double.program,
+            &options.double.depth_uniform_location,
+        ),
+        (
+            &options.perturbation.program,
+            &options.perturbation.depth_uniform_location,
+        ),
+        (
+            &options.newton.program,
+            &options.newton.depth_uniform_location,
+        ),
+        (
+            &options.lyapunov.program,
+            &options.lyapunov.depth_uniform_location,
+        ),
+    ]
+    .into_iter()
+    .chain(variants)
+    {
+        context.use_program(Some(program));
+        context.uniform1i(Some(location), depth as i32);
+    }
+    options.depth = depth;
+}
+
+/// Switches every escape-time program between whole and continuous iteration counts
+///
+/// Smooth colouring needs a bailout of at least [`crate::multibrot::SMOOTH_BAILOUT`] to be continuous, which
+/// `double.frag` and `perturbation.frag` take care of themselves but `shader.frag` takes from [`set_formula`]
+pub fn set_smooth(context: &WebGl2RenderingContext, options: &mut CanvasState, smooth: bool) {
+    if options.smooth == smooth {
+        return;
+    }
+    let variants = options
+        .variants()
+        .map(|variant| (&variant.program, &variant.smooth_uniform_location));
+    for (program, location) in [
+        (
+            &options.single.program,
+            &options.single.smooth_uniform_location,
+        ),
+        (
+            &options.double.program,
+            &options.double.smooth_uniform_location,
+        ),
+        (
+            &options.perturbation.program,
+            &options.perturbation.smooth_uniform_location,
+        ),
+    ]
+    .into_iter()
+    .chain(variants)
+    {
+        context.use_program(Some(program));
+        context.uniform1i(Some(location), smooth as i32);
+    }
+    options.smooth = smooth;
+}
+
+/// Draws lines `thickness` pixels wide where points are estimated to be that close to the set, or turns them off
+/// for 0
+///
+/// `shader.frag` and `double.frag` track the derivative of each orbit for the estimate while they're on, which
+/// needs a bailout of at least [`crate::multibrot::SMOOTH_BAILOUT`] to be accurate. `perturbation.frag` and custom
+/// formulas don't estimate distances, so they're drawn without lines
+pub fn set_line_thickness(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    thickness: f64,
+) {
+    let estimate = thickness > 0.0;
+    if estimate != (options.line_thickness > 0.0) {
+        let variants = options.variants().filter_map(|variant| {
+            Some((
+                &variant.program,
+                variant.distance_uniform_location.as_ref()?,
+            ))
+        });
+        for (program, location) in [
+            (
+                &options.single.program,
+                options.single.distance_uniform_location.as_ref().unwrap(),
+            ),
+            (
+                &options.double.program,
+                &options.double.distance_uniform_location,
+            ),
+        ]
+        .into_iter()
+        .chain(variants)
+        {
+            context.use_program(Some(program));
+            context.uniform1i(Some(location), estimate as i32);
+        }
+    }
+    options.line_thickness = thickness;
+}
+
+/// Switches every escape-time program to the Julia set for `c`, or back to the Mandelbrot set for `None`
+pub fn set_julia(
+    context: &WebGl2RenderingContext,
+    options: &CanvasState,
+    julia: Option<(f64, f64)>,
+) {
+    let (c_re, c_im) = julia.unwrap_or_default();
+    let variants = options
+        .variants()
+        .map(|variant| (&variant.program, &variant.julia));
+    for (program, uniforms) in [
+        (&options.single.program, &options.single.julia),
+        (&options.perturbation.program, &options.perturbation.julia),
+    ]
+    .into_iter()
+    .chain(variants)
+    {
+        context.use_program(Some(program));
+        context.uniform1i(
+            Some(&uniforms.julia_uniform_location),
+            julia.is_some() as i32,
+        );
+        context.uniform2f(Some(&uniforms.c_uniform_location), c_re as f32, c_im as f32);
+    }
+    // `double.frag` takes c as double-single values
+    let [re_hi, re_lo] = split_f64(c_re);
+    let [im_hi, im_lo] = split_f64(c_im);
+    context.use_program(Some(&options.double.program));
+    context.uniform1i(
+        Some(&options.double.julia.julia_uniform_location),
+        julia.is_some() as i32,
+    );
+    context.uniform4f(
+        Some(&options.double.julia.c_uniform_location),
+        re_hi,
+        re_lo,
+        im_hi,
+        im_lo,
+    );
+}
+
+/// Sets the formula iterated, the power z is raised to each iteration and the radius beyond which it escapes
+///
+/// Only the single precision program supports formulas other than the Mandelbrot set with a power of 2, and
+/// [`Formula::Custom`] and [`Formula::Hybrid`] draw whatever was last given to [`compile_custom`] and
+/// [`compile_hybrid`]
+pub fn set_formula(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    formula: Formula,
+    power: f64,
+    escape_radius: f64,
+) {
+    for program in [&options.single].into_iter().chain(options.variants()) {
+        context.use_program(Some(&program.program));
+        context.uniform1i(program.formula_uniform_location.as_ref(), formula.id());
+        context.uniform1f(Some(&program.power_uniform_location), power as f32);
+        context.uniform1f(
+            Some(&program.escape_radius_uniform_location),
+            escape_radius as f32,
+        );
     }
+    options.formula = formula;
+}
+
+/// Sets how `shader.frag` colours points which don't escape, see [`Interior`]
+pub fn set_interior(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    interior: Interior,
+) {
+    if options.interior != interior {
+        context.use_program(Some(&options.single.program));
+        context.uniform1i(
+            options.single.interior_uniform_location.as_ref(),
+            interior.id(),
+        );
+        options.interior = interior;
+    }
+}
+
+/// Builds a variant of `shader.frag` which iterates `z = formula`, where `formula` is a GLSL `vec2` expression of
+/// `z` and `c` as made by [`crate::expression::to_glsl`]
+///
+/// Compile and link errors are returned with the info log, leaving the last custom formula in place
+pub fn compile_custom(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    formula: &str,
+) -> Result<(), String> {
+    options.custom = Some(compile_variant(
+        context,
+        options,
+        &format!(
+            "#define CUSTOM_FORMULA\n\
+             vec2 custom_formula(vec2 z, vec2 c) {{ return {formula}; }}"
+        ),
+    )?);
+    Ok(())
+}
+
+/// Builds a variant of `shader.frag` which cycles through the steps of `hybrid`, see [`Hybrid::to_glsl`]
+pub fn compile_hybrid(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    hybrid: &Hybrid,
+) -> Result<(), String> {
+    options.hybrid = Some(compile_variant(context, options, &hybrid.to_glsl())?);
+    Ok(())
+}
+
+/// Builds `shader.frag` with `formula.glsl` filled in by `snippet`
+fn compile_variant(
+    context: &WebGl2RenderingContext,
+    options: &CanvasState,
+    snippet: &str,
+) -> Result<SingleProgram, String> {
+    let source = include_str!("shader.frag").replace("#include \"formula.glsl\"", snippet);
+    let frag_shader = compile_shader(context, WebGl2RenderingContext::FRAGMENT_SHADER, &source)?;
+    let program = link_program(context, &options.vert_shader, &frag_shader)?;
+    let variant = SingleProgram::new(context, program);
+    context.use_program(Some(&variant.program));
+    context.uniform1i(Some(&variant.depth_uniform_location), options.depth as i32);
+    context.uniform1i(
+        Some(&variant.smooth_uniform_location),
+        options.smooth as i32,
+    );
+    if let Some(location) = &variant.distance_uniform_location {
+        context.uniform1i(Some(location), (options.line_thickness > 0.0) as i32);
+    }
+    Ok(variant)
+}
+
+/// Sets the polynomial [`Formula::Newton`] draws Newton's method for, along with its roots
+pub fn set_polynomial(
+    context: &WebGl2RenderingContext,
+    options: &CanvasState,
+    polynomial: &Polynomial,
+    roots: &[[f64; 2]],
+) {
+    let mut coefficients = [0.0; (MAX_DEGREE + 1) * 2];
+    for (value, coefficient) in coefficients
+        .iter_mut()
+        .zip(polynomial.coefficients.iter().flatten())
+    {
+        *value = *coefficient as f32;
+    }
+    let mut root_values = [0.0; MAX_DEGREE * 2];
+    for (value, root) in root_values.iter_mut().zip(roots.iter().flatten()) {
+        *value = *root as f32;
+    }
+    context.use_program(Some(&options.newton.program));
+    context.uniform1i(
+        Some(&options.newton.degree_uniform_location),
+        polynomial.degree() as i32,
+    );
+    context.uniform2fv_with_f32_array(
+        Some(&options.newton.coefficients_uniform_location),
+        &coefficients,
+    );
+    context.uniform2fv_with_f32_array(Some(&options.newton.roots_uniform_location), &root_values);
+}
+
+/// Sets the sequence of A and B rates [`Formula::Lyapunov`] cycles through, with true for each B
+pub fn set_sequence(context: &WebGl2RenderingContext, options: &CanvasState, sequence: &[bool]) {
+    let mut values = [0; MAX_SEQUENCE];
+    for (value, b) in values.iter_mut().zip(sequence) {
+        *value = *b as i32;
+    }
+    context.use_program(Some(&options.lyapunov.program));
+    context.uniform1i(
+        Some(&options.lyapunov.sequence_length_uniform_location),
+        sequence.len() as i32,
+    );
+    context.uniform1iv_with_i32_array(Some(&options.lyapunov.sequence_uniform_location), &values);
+}
+
+/// Sets the size of the tiles later passes are split into
+pub fn set_tile_size(options: &mut CanvasState, size: i32) {
+    options.tile_size = size;
+}
+
+/// Resizes the iteration buffers to match the canvas
+pub fn resize_buffers(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    width: i32,
+    height: i32,
+) {
+    options.canvas_width = width;
+    options.canvas_height = height;
+    reallocate_buffers(context, options);
+}
+
+/// Renders later passes at `resolution` times the size of the canvas, to be scaled up when presented
+pub fn set_resolution(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    resolution: f64,
+) {
+    if options.resolution != resolution {
+        options.resolution = resolution;
+        reallocate_buffers(context, options);
+    }
+}
+
+pub fn resolution(options: &CanvasState) -> f64 {
+    options.resolution
+}
+
+/// Draws later passes into part of the buffers, given as `[x, y, width, height]` in fractions of their size
+///
+/// The screen space of the shaders covers just the region, so the views drawn into it need its aspect ratio
+pub fn set_region(options: &mut CanvasState, region: [f64; 4]) {
+    options.region = region;
+}
+
+/// Region set by [`set_region`] in pixels of the iteration buffers
+fn region_pixels(options: &CanvasState) -> Tile {
+    let [x, y, width, height] = options.region;
+    let left = (x * options.width as f64).round() as i32;
+    let bottom = (y * options.height as f64).round() as i32;
+    let right = ((x + width) * options.width as f64).round() as i32;
+    let top = ((y + height) * options.height as f64).round() as i32;
+    [left, bottom, (right - left).max(1), (top - bottom).max(1)]
+}
+
+/// Size of the region passes draw into, see [`set_region`] and [`read_iterations`]
+pub fn region_size(options: &CanvasState) -> (usize, usize) {
+    let [_, _, width, height] = region_pixels(options);
+    (width as usize, height as usize)
+}
+
+/// Resizes the iteration buffers to the canvas size times the resolution, abandoning any pass in progress
+///
+/// The last finished pass is scaled into the new front buffer, so it can still be shown while the next one is drawn
+fn reallocate_buffers(context: &WebGl2RenderingContext, options: &mut CanvasState) {
+    let width = ((options.canvas_width as f64 * options.resolution).round() as i32).max(1);
+    let height = ((options.canvas_height as f64 * options.resolution).round() as i32).max(1);
+    options.pass = None;
+    if (width, height) == (options.width, options.height) {
+        return;
+    }
+    let back = 1 - options.front;
+    options.buffers[back].resize(context, width, height);
+    if options.width > 0 && options.height > 0 {
+        context.bind_framebuffer(
+            WebGl2RenderingContext::READ_FRAMEBUFFER,
+            Some(&options.buffers[options.front].framebuffer),
+        );
+        context.bind_framebuffer(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            Some(&options.buffers[back].framebuffer),
+        );
+        context.blit_framebuffer(
+            0,
+            0,
+            options.width,
+            options.height,
+            0,
+            0,
+            width,
+            height,
+            WebGl2RenderingContext::COLOR_BUFFER_BIT,
+            WebGl2RenderingContext::NEAREST,
+        );
+        context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    }
+    options.buffers[options.front].resize(context, width, height);
+    options.front = back;
+    options.width = width;
+    options.height = height;
+}
+
+/// Starts a pass with `program`, replacing any pass in progress
+///
+/// The back buffer starts as a copy of the front, so tiles which haven't been drawn yet show the last finished pass
+fn begin_pass(context: &WebGl2RenderingContext, options: &mut CanvasState, program: &WebGlProgram) {
+    context.bind_framebuffer(
+        WebGl2RenderingContext::READ_FRAMEBUFFER,
+        Some(&options.buffers[options.front].framebuffer),
+    );
+    context.bind_framebuffer(
+        WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+        Some(&options.buffers[1 - options.front].framebuffer),
+    );
+    context.blit_framebuffer(
+        0,
+        0,
+        options.width,
+        options.height,
+        0,
+        0,
+        options.width,
+        options.height,
+        WebGl2RenderingContext::COLOR_BUFFER_BIT,
+        WebGl2RenderingContext::NEAREST,
+    );
+    context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    context.use_program(Some(program));
+    options.colouring = if *program == options.raymarch.program {
+        Colouring::Shaded
+    } else if *program == options.lyapunov.program {
+        Colouring::Lyapunov
+    } else {
+        Colouring::Escape
+    };
+    options.interior_colouring = if *program == options.single.program {
+        options.interior
+    } else {
+        Interior::Flat
+    };
+    let [x, y, width, height] = region_pixels(options);
+    options.pass = Some(PendingPass {
+        program: program.clone(),
+        region: [x, y, width, height],
+        tiles: split(width, height, options.tile_size)
+            .into_iter()
+            .map(|[tile_x, tile_y, tile_width, tile_height]| {
+                [tile_x + x, tile_y + y, tile_width, tile_height]
+            })
+            .collect(),
+        next: 0,
+    });
 }
 
-pub fn draw(context: &WebGl2RenderingContext, options: &CanvasState, transform: &[f32; 9]) {
-    context.clear_color(0.0, 0.0, 0.0, 1.0);
-    context.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+/// Draws tiles of the pending pass until they add up to `work` pixels times iterations, or at least one tile
+///
+/// Returns true once every tile has been drawn, at which point the back and front buffers are swapped
+pub fn draw_tiles(context: &WebGl2RenderingContext, options: &mut CanvasState, work: f64) -> bool {
+    let Some(pass) = options.pass.as_mut() else {
+        return true;
+    };
+    context.bind_framebuffer(
+        WebGl2RenderingContext::FRAMEBUFFER,
+        Some(&options.buffers[1 - options.front].framebuffer),
+    );
+    let [x, y, width, height] = pass.region;
+    context.viewport(x, y, width, height);
+    context.use_program(Some(&pass.program));
+    context.active_texture(WebGl2RenderingContext::TEXTURE0 + ITERATIONS_TEXTURE_UNIT);
+    context.bind_texture(
+        WebGl2RenderingContext::TEXTURE_2D,
+        Some(&options.buffers[options.front].texture),
+    );
+    context.active_texture(WebGl2RenderingContext::TEXTURE0 + ORBIT_TEXTURE_UNIT);
+    context.bind_texture(
+        WebGl2RenderingContext::TEXTURE_2D,
+        Some(&options.perturbation.orbit_texture),
+    );
+    context.enable(WebGl2RenderingContext::SCISSOR_TEST);
+    let mut done = 0.0;
+    while done < work && pass.next < pass.tiles.len() {
+        let [x, y, width, height] = pass.tiles[pass.next];
+        context.scissor(x, y, width, height);
+        context.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, options.vertex_count);
+        done += (width * height) as f64 * options.depth as f64;
+        pass.next += 1;
+    }
+    context.disable(WebGl2RenderingContext::SCISSOR_TEST);
+    context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    if pass.next < pass.tiles.len() {
+        return false;
+    }
+    options.pass = None;
+    options.front = 1 - options.front;
+    true
+}
+
+/// Proportion of the tiles of the pending pass drawn so far, if there is one
+pub fn pass_progress(options: &CanvasState) -> Option<f32> {
+    let pass = options.pass.as_ref()?;
+    Some(pass.next as f32 / pass.tiles.len() as f32)
+}
+
+/// Colours the output of the last pass onto the canvas, including any tiles drawn so far of the pending pass
+pub fn present(context: &WebGl2RenderingContext, options: &CanvasState) {
+    let buffer = if options.pass.is_some() {
+        1 - options.front
+    } else {
+        options.front
+    };
+    context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    context.viewport(0, 0, options.canvas_width, options.canvas_height);
+    context.use_program(Some(&options.colour));
+    context.uniform1i(
+        Some(&options.colouring_uniform_location),
+        options.colouring as i32,
+    );
+    context.uniform1f(
+        Some(&options.line_thickness_uniform_location),
+        options.line_thickness as f32,
+    );
+    context.uniform1f(
+        Some(&options.buffer_scale_uniform_location),
+        (1.0 / options.resolution) as f32,
+    );
+    context.uniform1i(
+        Some(&options.interior_uniform_location),
+        options.interior_colouring.id(),
+    );
+    context.active_texture(WebGl2RenderingContext::TEXTURE0 + ITERATIONS_TEXTURE_UNIT);
+    context.bind_texture(
+        WebGl2RenderingContext::TEXTURE_2D,
+        Some(&options.buffers[buffer].texture),
+    );
+    context.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, options.vertex_count);
+}
+
+/// Shows RGBA pixels made on the CPU, top row first, stretched over the canvas
+pub fn present_image(
+    context: &WebGl2RenderingContext,
+    options: &CanvasState,
+    width: usize,
+    height: usize,
+    pixels: &[u8],
+) {
+    context.active_texture(WebGl2RenderingContext::TEXTURE0 + IMAGE_TEXTURE_UNIT);
+    context.bind_texture(
+        WebGl2RenderingContext::TEXTURE_2D,
+        Some(&options.image_texture),
+    );
+    context
+        .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA8 as i32,
+            width as i32,
+            height as i32,
+            0,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            Some(pixels),
+        )
+        .unwrap();
+    context.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    context.viewport(0, 0, options.canvas_width, options.canvas_height);
+    context.use_program(Some(&options.image));
+    context.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, options.vertex_count);
+}
+
+/// Reads back the region of the output of the last pass set by [`set_region`], see [`IterationBuffer::read`]
+pub fn read_iterations(context: &WebGl2RenderingContext, options: &CanvasState) -> Vec<f32> {
+    options.buffers[options.front].read(context, region_pixels(options))
+}
+
+/// Sets the palette `colour.frag` colours escape ratios with, which takes effect the next time the buffers are
+/// presented
+pub fn set_palette(context: &WebGl2RenderingContext, options: &CanvasState, palette: &Palette) {
+    context.active_texture(WebGl2RenderingContext::TEXTURE0 + PALETTE_TEXTURE_UNIT);
+    context.bind_texture(
+        WebGl2RenderingContext::TEXTURE_2D,
+        Some(&options.palette_texture),
+    );
+    context
+        .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA8 as i32,
+            PALETTE_SIZE as i32,
+            1,
+            0,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            Some(palette.texels()),
+        )
+        .unwrap();
+    let uniforms = &options.palette;
+    context.use_program(Some(&options.colour));
+    context.uniform1f(
+        Some(&uniforms.offset_uniform_location),
+        palette.mapping.offset,
+    );
+    context.uniform1f(
+        Some(&uniforms.scale_uniform_location),
+        palette.mapping.scale,
+    );
+    context.uniform1i(
+        Some(&uniforms.repeat_uniform_location),
+        palette.mapping.repeat as i32,
+    );
+    context.uniform1i(
+        Some(&uniforms.reverse_uniform_location),
+        palette.mapping.reverse as i32,
+    );
+}
+
+/// Uploads a reference orbit for [`draw_perturbation`] to iterate relative to
+pub fn upload_orbit(
+    context: &WebGl2RenderingContext,
+    options: &CanvasState,
+    orbit: &ReferenceOrbit,
+) {
+    let (data, rows) = orbit.texture_data();
+    context.active_texture(WebGl2RenderingContext::TEXTURE0 + ORBIT_TEXTURE_UNIT);
+    context.bind_texture(
+        WebGl2RenderingContext::TEXTURE_2D,
+        Some(&options.perturbation.orbit_texture),
+    );
+    context
+        .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RG32F as i32,
+            ORBIT_WIDTH as i32,
+            rows as i32,
+            0,
+            WebGl2RenderingContext::RG,
+            WebGl2RenderingContext::FLOAT,
+            Some(&js_sys::Float32Array::from(data.as_slice())),
+        )
+        .unwrap();
+    context.use_program(Some(&options.perturbation.program));
+    context.uniform1i(
+        Some(&options.perturbation.orbit_length_uniform_location),
+        orbit.points.len() as i32,
+    );
+    context.uniform2f(
+        Some(&options.perturbation.reference_uniform_location),
+        orbit.center.0.to_f64() as f32,
+        orbit.center.1.to_f64() as f32,
+    );
+}
+
+/// Starts a single precision pass of the formula last given to [`set_formula`], see [`draw_tiles`]
+pub fn draw(context: &WebGl2RenderingContext, options: &mut CanvasState, transform: &[f32; 9]) {
+    let (program, transform_location) = match (options.formula, &options.custom, &options.hybrid) {
+        (Formula::Newton, ..) => (
+            &options.newton.program,
+            &options.newton.transform_uniform_location,
+        ),
+        (Formula::Lyapunov, ..) => (
+            &options.lyapunov.program,
+            &options.lyapunov.transform_uniform_location,
+        ),
+        (Formula::Custom, Some(custom), _) => (&custom.program, &custom.transform_uniform_location),
+        (Formula::Hybrid, _, Some(hybrid)) => (&hybrid.program, &hybrid.transform_uniform_location),
+        _ => (
+            &options.single.program,
+            &options.single.transform_uniform_location,
+        ),
+    };
+    let (program, transform_location) = (program.clone(), transform_location.clone());
+    begin_pass(context, options, &program);
+    context.uniform_matrix3fv_with_f32_array(Some(&transform_location), false, transform);
+}
+
+/// Starts a pass ray marching `shape` from `eye`, where `transform` takes each pixel to the direction of its ray
+pub fn draw_raymarch(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    shape: Shape,
+    power: f64,
+    eye: [f64; 3],
+    transform: &[f32; 9],
+) {
+    let program = options.raymarch.clone();
+    begin_pass(context, options, &program.program);
+    let [x, y, z] = eye.map(|value| value as f32);
+    context.uniform3f(Some(&program.eye_uniform_location), x, y, z);
     context.uniform_matrix3fv_with_f32_array(
-        Some(&options.transform_uniform_location),
+        Some(&program.transform_uniform_location),
         false,
         transform,
     );
-    context.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, options.vertex_count);
+    context.uniform1i(Some(&program.shape_uniform_location), shape.id());
+    context.uniform1f(Some(&program.power_uniform_location), power as f32);
+}
+
+/// Starts a double-single precision pass, where pixel `p` is at `center + (screen_transform * p) * scale`
+pub fn draw_double(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    center: (f64, f64),
+    scale: f64,
+    screen_transform: &[f32; 9],
+) {
+    let program = options.double.program.clone();
+    begin_pass(context, options, &program);
+    let [re_hi, re_lo] = split_f64(center.0);
+    let [im_hi, im_lo] = split_f64(center.1);
+    context.uniform4f(
+        Some(&options.double.center_uniform_location),
+        re_hi,
+        re_lo,
+        im_hi,
+        im_lo,
+    );
+    let [scale_hi, scale_lo] = split_f64(scale);
+    context.uniform2f(
+        Some(&options.double.scale_uniform_location),
+        scale_hi,
+        scale_lo,
+    );
+    context.uniform_matrix3fv_with_f32_array(
+        Some(&options.double.screen_transform_uniform_location),
+        false,
+        screen_transform,
+    );
+}
+
+/// Starts a pass relative to the last orbit passed to [`upload_orbit`], where pixel `p` is at `reference + (delta_transform * p) * 2^scale_exponent`
+///
+/// When `correcting`, only the pixels the previous pass marked as glitched are drawn
+pub fn draw_perturbation(
+    context: &WebGl2RenderingContext,
+    options: &mut CanvasState,
+    delta_transform: &[f32; 9],
+    scale_exponent: i32,
+    series: &SeriesApproximation,
+    correcting: bool,
+) {
+    let program = options.perturbation.program.clone();
+    begin_pass(context, options, &program);
+    context.uniform_matrix3fv_with_f32_array(
+        Some(&options.perturbation.delta_transform_uniform_location),
+        false,
+        delta_transform,
+    );
+    context.uniform1i(
+        Some(&options.perturbation.scale_exponent_uniform_location),
+        scale_exponent,
+    );
+    context.uniform1i(
+        Some(&options.perturbation.skip_uniform_location),
+        series.skip as i32,
+    );
+    context.uniform2fv_with_f32_array(
+        Some(&options.perturbation.series_uniform_location),
+        series.coefficients.as_flattened(),
+    );
+    context.uniform1i(
+        Some(&options.perturbation.series_exponent_uniform_location),
+        series.exponent,
+    );
+    context.uniform1i(
+        Some(&options.perturbation.correcting_uniform_location),
+        correcting as i32,
+    );
 }
@@ -0,0 +1,46 @@
+#version 300 es
+
+precision highp float;
+
+in vec2 vPos;
+
+// Must match MAX_DEGREE in newton.rs
+const int MAX_DEGREE = 16;
+// How close z has to get to a root to count as converged
+const float TOLERANCE = 1.0e-4;
+
+uniform int depth;
+uniform mat3 transform;
+uniform int degree;
+// Coefficients of the polynomial from the constant term up
+uniform vec2 coefficients[MAX_DEGREE + 1];
+uniform vec2 roots[MAX_DEGREE];
+
+// Iterations taken as a ratio of depth in x, and in z the hue of the root converged to, or 0 if it didn't
+out vec4 outValue;
+
+#include "complex.glsl"
+
+vec4 newton(vec2 z) {
+    for (int i = 0; i < depth; i++) {
+        // Horner's method for p(z) and p'(z) together
+        vec2 p = coefficients[degree];
+        vec2 derivative = vec2(0.0);
+        for (int n = degree - 1; n >= 0; n--) {
+            derivative = cmul(derivative, z) + p;
+            p = cmul(p, z) + coefficients[n];
+        }
+        if (derivative == vec2(0.0)) break;
+        z -= cdiv(p, derivative);
+        for (int n = 0; n < degree; n++) {
+            if (distance(z, roots[n]) < TOLERANCE) {
+                return vec4(float(i) / float(depth), 0.0, float(n + 1) / float(degree), 1.0);
+            }
+        }
+    }
+    return vec4(1.0, 0.0, 0.0, 1.0);
+}
+
+void main() {
+    outValue = newton((vec3(vPos.x, vPos.y, 1.0) * transform).xy);
+}
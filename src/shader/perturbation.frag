@@ -0,0 +1,113 @@
+#version 300 es
+
+precision highp float;
+precision highp sampler2D;
+
+in vec2 vPos;
+
+uniform int depth;
+// Maps the screen to the offset from the reference point, in units of 2^scale_exponent
+uniform mat3 delta_transform;
+uniform int scale_exponent;
+// Reference orbit z_0..z_(orbit_length - 1), packed into rows of ORBIT_WIDTH texels
+uniform sampler2D orbit;
+uniform int orbit_length;
+// Reference point rounded to single precision, only used once the orbit runs out
+uniform vec2 reference;
+// When set, the orbit starts at the reference and c is added each iteration, so pixels only differ in z_0
+uniform bool julia;
+uniform vec2 c;
+// Series approximation of the delta after the first skip iterations, in units of 2^series_exponent
+uniform int skip;
+uniform vec2 series[3];
+uniform int series_exponent;
+// When set, only pixels marked as glitched in the previous pass are iterated again
+uniform bool correcting;
+uniform sampler2D previous;
+
+// Escape ratio in x and whether the pixel is glitched in y
+// Distances aren't estimated this deep, as the derivative would overflow single precision, so w is always -1
+out vec4 outValue;
+
+#include "escape.glsl"
+
+const int ORBIT_WIDTH = 1024;
+// Below this exponent deltas would underflow, so they're kept as a mantissa and shared exponent instead
+const int DIRECT_EXPONENT = -100;
+// Pauldelbrot's criterion, a pixel is glitched once |z| < 10^-3 |Z| (compared squared)
+const float GLITCH_TOLERANCE = 1.0e-6;
+
+vec2 reference_orbit(int n) {
+    return texelFetch(orbit, ivec2(n % ORBIT_WIDTH, n / ORBIT_WIDTH), 0).xy;
+}
+
+vec2 mul(vec2 a, vec2 b) {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// Escape ratio of a pixel whose orbit got beyond 2 on iteration i, reaching z
+// Smooth colouring needs it to get much further, which it does without the reference as z is large enough by now
+float escaped(int i, vec2 z, vec2 offset) {
+    if (!smooth_colouring) return escape_ratio(i, z, 2.0, 2.0);
+    for (; dot(z, z) <= SMOOTH_BAILOUT * SMOOTH_BAILOUT && i + 1 < depth; i++) {
+        z = mul(z, z) + offset;
+    }
+    return escape_ratio(i, z, SMOOTH_BAILOUT, 2.0);
+}
+
+vec2 pixel(vec2 d) {
+    int i = skip;
+    vec2 dc = julia ? vec2(0.0) : d * exp2(float(scale_exponent));
+    // The pixel rounded to single precision, which is plenty once its orbit has escaped
+    vec2 offset = julia ? c : reference + dc;
+    // Rescaled iterations: dz = w * 2^e and dc = dc_rescaled * 2^e, where w stays close to 1
+    vec2 d_squared = mul(d, d);
+    vec2 w = mul(series[0], d) + mul(series[1], d_squared) + mul(series[2], mul(d_squared, d));
+    int e = series_exponent;
+    vec2 dc_rescaled = julia ? vec2(0.0) : d * exp2(float(scale_exponent - series_exponent));
+    for (; i < depth && e < DIRECT_EXPONENT; i++) {
+        if (i + 1 >= orbit_length) return vec2(float(i) / float(depth), 0.0);
+        w = 2.0 * mul(reference_orbit(i), w) + mul(w, w) * exp2(float(e)) + dc_rescaled;
+        // While the delta is this small, the pixel escapes with the reference
+        vec2 z = reference_orbit(i + 1);
+        if (dot(z, z) > 4.0) return vec2(escaped(i, z, offset), 0.0);
+        float magnitude = max(abs(w.x), abs(w.y));
+        if (magnitude > 4294967296.0 || (magnitude > 0.0 && magnitude < 1.0 / 4294967296.0)) {
+            int shift = int(floor(log2(magnitude)));
+            w *= exp2(float(-shift));
+            dc_rescaled *= exp2(float(-shift));
+            e += shift;
+        }
+    }
+    vec2 dz = w * exp2(float(e));
+    for (; i < depth; i++) {
+        if (i + 1 >= orbit_length) break;
+        dz = 2.0 * mul(reference_orbit(i), dz) + mul(dz, dz) + dc;
+        vec2 reference_z = reference_orbit(i + 1);
+        vec2 z = reference_z + dz;
+        if (dot(z, z) > 4.0) return vec2(escaped(i, z, offset), 0.0);
+        if (dot(z, z) < GLITCH_TOLERANCE * dot(reference_z, reference_z)) {
+            return vec2(float(i) / float(depth), 1.0);
+        }
+    }
+    if (i >= depth) return vec2(1.0, 0.0);
+    // The reference escaped first, so this pixel needs another reference
+    // Finish it without one in the meantime, in case no better reference is found
+    vec2 z = reference_orbit(i) + dz;
+    for (; i < depth; i++) {
+        z = mul(z, z) + offset;
+        if (dot(z, z) > 4.0) return vec2(escaped(i, z, offset), 1.0);
+    }
+    return vec2(1.0, 1.0);
+}
+
+void main() {
+    if (correcting) {
+        vec4 previous_value = texelFetch(previous, ivec2(gl_FragCoord.xy), 0);
+        if (previous_value.y == 0.0) {
+            outValue = previous_value;
+            return;
+        }
+    }
+    outValue = vec4(pixel((vec3(vPos.x, vPos.y, 1.0) * delta_transform).xy), 0.0, -1.0);
+}
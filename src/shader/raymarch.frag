@@ -0,0 +1,135 @@
+#version 300 es
+
+precision highp float;
+
+in vec2 vPos;
+
+// Position of the camera and the transform from screen space to the direction of the ray through each pixel
+uniform vec3 eye;
+uniform mat3 transform;
+// Shape to draw, see Shape in raymarch.rs
+uniform int shape;
+// Power of the Mandelbulb, or scale of the Mandelbox
+uniform float power;
+
+// Lit colour in rgb, shown as it is by colour.frag
+out vec4 outValue;
+
+const int MANDELBOX = 1;
+
+const int MAX_STEPS = 256;
+const float MAX_DISTANCE = 200.0;
+// A ray hits the surface once it's closer than this fraction of the distance it has travelled, about a pixel
+const float HIT_RATIO = 0.0005;
+const int BULB_ITERATIONS = 12;
+const int BOX_ITERATIONS = 16;
+
+const vec3 LIGHT = vec3(0.577, 0.577, 0.577);
+
+// Distance estimate of the Mandelbulb, with the Mandelbrot set's axis pointing up along y
+float mandelbulb(vec3 pos, out float trap) {
+    pos = pos.xzy;
+    vec3 z = pos;
+    float dr = 1.0;
+    float r = length(z);
+    trap = r;
+    for (int i = 0; i < BULB_ITERATIONS && r < 2.0; i++) {
+        // Raise z to the power in spherical coordinates
+        float theta = acos(clamp(z.z / max(r, 1.0e-12), -1.0, 1.0)) * power;
+        float phi = atan(z.y, z.x) * power;
+        dr = pow(r, power - 1.0) * power * dr + 1.0;
+        z = pow(r, power) * vec3(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta)) + pos;
+        r = length(z);
+        trap = min(trap, r);
+    }
+    return 0.5 * log(r) * r / dr;
+}
+
+// Distance estimate of the Mandelbox, using the power as its scale
+float mandelbox(vec3 pos, out float trap) {
+    vec3 z = pos;
+    float dr = 1.0;
+    trap = length(z);
+    for (int i = 0; i < BOX_ITERATIONS; i++) {
+        // Box fold, then sphere fold with a minimum radius of 0.5
+        z = clamp(z, -1.0, 1.0) * 2.0 - z;
+        float r2 = dot(z, z);
+        if (r2 < 0.25) {
+            z *= 4.0;
+            dr *= 4.0;
+        } else if (r2 < 1.0) {
+            z /= r2;
+            dr /= r2;
+        }
+        z = z * power + pos;
+        dr = dr * abs(power) + 1.0;
+        trap = min(trap, length(z));
+    }
+    return length(z) / abs(dr);
+}
+
+float estimate(vec3 pos, out float trap) {
+    if (shape == MANDELBOX) return mandelbox(pos, trap);
+    return mandelbulb(pos, trap);
+}
+
+vec3 normal(vec3 pos, float epsilon) {
+    // Tetrahedral differences take four estimates rather than six
+    vec2 k = vec2(1.0, -1.0);
+    float trap;
+    return normalize(
+        k.xyy * estimate(pos + k.xyy * epsilon, trap) +
+        k.yyx * estimate(pos + k.yyx * epsilon, trap) +
+        k.yxy * estimate(pos + k.yxy * epsilon, trap) +
+        k.xxx * estimate(pos + k.xxx * epsilon, trap)
+    );
+}
+
+// How much of the sky is visible from a point, by how far the surface is along the normal at a few steps
+float occlusion(vec3 pos, vec3 surface, float spacing) {
+    float occluded = 0.0;
+    float weight = 1.0;
+    float trap;
+    for (int i = 1; i <= 5; i++) {
+        float offset = spacing * float(i);
+        occluded += weight * (offset - estimate(pos + surface * offset, trap));
+        weight *= 0.5;
+    }
+    return clamp(1.0 - occluded / spacing, 0.0, 1.0);
+}
+
+vec3 sky(vec3 direction) {
+    return mix(vec3(0.02, 0.02, 0.05), vec3(0.15, 0.2, 0.35), direction.y * 0.5 + 0.5);
+}
+
+void main() {
+    vec3 direction = normalize(vec3(vPos.x, vPos.y, 1.0) * transform);
+    float travelled = 0.0;
+    float trap = 0.0;
+    bool hit = false;
+    int steps = 0;
+    for (; steps < MAX_STEPS; steps++) {
+        float gap = estimate(eye + direction * travelled, trap);
+        if (gap < HIT_RATIO * travelled) {
+            hit = true;
+            break;
+        }
+        travelled += gap;
+        if (travelled > MAX_DISTANCE) break;
+    }
+    vec3 colour;
+    if (hit) {
+        vec3 pos = eye + direction * travelled;
+        vec3 surface = normal(pos, HIT_RATIO * travelled);
+        // Points whose orbits stay close to the origin are coloured warmer
+        vec3 albedo = mix(vec3(0.9, 0.55, 0.25), vec3(0.3, 0.55, 0.9), clamp(trap, 0.0, 1.0));
+        float diffuse = max(dot(surface, LIGHT), 0.0);
+        float specular = pow(max(dot(surface, normalize(LIGHT - direction)), 0.0), 32.0);
+        float ambient = occlusion(pos, surface, 0.02 * travelled);
+        colour = albedo * (0.25 * ambient + 0.75 * diffuse) + 0.3 * specular;
+    } else {
+        // Rays passing close to the surface pick up a glow from the steps they took
+        colour = sky(direction) + vec3(0.4, 0.5, 0.8) * pow(float(steps) / float(MAX_STEPS), 2.0);
+    }
+    outValue = vec4(pow(colour, vec3(1.0 / 2.2)), 1.0);
+}
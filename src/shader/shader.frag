@@ -6,8 +6,23 @@ in vec2 vPos;
 
 uniform int depth;
 uniform mat3 transform;
+// When set, z starts at the pixel and c is added each iteration instead of the pixel
+uniform bool julia;
+uniform vec2 c;
+// Each iteration raises z to this power, which can be fractional
+uniform float power;
+uniform float escape_radius;
+// Variation on z^power + c to draw, see Formula in formula.rs
+uniform int formula;
+// How points which don't escape are coloured, see Interior in interior.rs
+uniform int interior;
 
-out vec4 outColor;
+// Escape ratio in x and the distance estimate in pixels in w, or -1 if there isn't one
+// Points which don't escape have what their interior mode needs instead, see inside()
+out vec4 outValue;
+
+#include "complex.glsl"
+#include "escape.glsl"
 
 vec2 square(vec2 im) {
     return vec2(
@@ -16,56 +31,178 @@ vec2 square(vec2 im) {
     );
 }
 
-float pixel(vec2 pos) {
-    vec2 z = vec2(0, 0);
+vec2 raise(vec2 z) {
+    if (power == 2.0) return square(z);
+    if (power == floor(power)) {
+        vec2 result = z;
+        for (int n = 1; n < int(power); n++) {
+            result = vec2(result.x * z.x - result.y * z.y, result.x * z.y + result.y * z.x);
+        }
+        return result;
+    }
+    // Fractional powers go through polar form, using the principal branch
+    float r = length(z);
+    if (r == 0.0) return vec2(0.0);
+    float angle = atan(z.y, z.x) * power;
+    return pow(r, power) * vec2(cos(angle), sin(angle));
+}
+
+const int MANDELBROT = 0;
+const int BURNING_SHIP = 1;
+const int TRICORN = 2;
+const int CELTIC = 3;
+const int BUFFALO = 4;
+
+vec2 iterate(vec2 z, int variation) {
+    if (variation == BURNING_SHIP) return raise(abs(z));
+    if (variation == TRICORN) return raise(vec2(z.x, -z.y));
+    vec2 raised = raise(z);
+    if (variation == CELTIC) return vec2(abs(raised.x), raised.y);
+    if (variation == BUFFALO) return abs(raised);
+    return raised;
+}
+
+// Reflects each component of dz where that component of z is negative, which is how abs(z) changes a derivative
+vec2 mirror(vec2 z, vec2 dz) {
+    return vec2(z.x < 0.0 ? -dz.x : dz.x, z.y < 0.0 ? -dz.y : dz.y);
+}
+
+// Derivative of iterate(z, variation) along dz
+// z^power scales and rotates it by power z^(power - 1), and the abs() of each variation reflects it
+vec2 derivative(vec2 z, vec2 dz, int variation) {
+    if (variation == BURNING_SHIP) {
+        dz = mirror(z, dz);
+        z = abs(z);
+    }
+    if (variation == TRICORN) {
+        z.y = -z.y;
+        dz.y = -dz.y;
+    }
+    vec2 raised = raise(z);
+    // z^power / z, which goes to 0 at 0 as the power is always above 1
+    vec2 result = dot(z, z) == 0.0 ? vec2(0.0) : power * cmul(cdiv(raised, z), dz);
+    if (variation == CELTIC) return vec2(raised.x < 0.0 ? -result.x : result.x, result.y);
+    if (variation == BUFFALO) return mirror(raised, result);
+    return result;
+}
+
+// Replaced by a user-defined or hybrid formula when one is compiled, see `compile_custom` and `compile_hybrid` in
+// mod.rs
+#include "formula.glsl"
+
+// Escape ratio in x and the distance estimate in y, leaving the last point of the orbit in z
+vec2 pixel(vec2 pos, out vec2 z) {
+    z = julia ? pos : vec2(0, 0);
+    vec2 offset = julia ? c : pos;
+    // Derivative of z with respect to c, or to the starting z for Julia sets
+    vec2 dz = julia ? vec2(1.0, 0.0) : vec2(0.0);
+    vec2 dc = julia ? vec2(0.0) : vec2(1.0, 0.0);
+#ifdef HYBRID_FORMULA
+    return hybrid(z, dz, offset, dc);
+#else
     for (int i = 0; i < depth; i++) {
-        z = square(z) + pos;
-        if (length(z) > 2.0) return float(i) / float(depth);
+#ifdef CUSTOM_FORMULA
+        // There's no derivative of a custom formula to estimate distances with
+        z = custom_formula(z, offset);
+        if (length(z) > escape_radius) return vec2(escape_ratio(i, z, escape_radius, power), -1.0);
+#else
+        if (distance_estimation) dz = derivative(z, dz, formula) + dc;
+        z = iterate(z, formula) + offset;
+        if (length(z) > escape_radius) {
+            return vec2(escape_ratio(i, z, escape_radius, power), distance_estimate(z, dz));
+        }
+#endif
+    }
+    return vec2(1.0, -1.0);
+#endif
+}
+
+const int FLAT = 0;
+const int PERIOD = 1;
+const int MULTIPLIER = 2;
+const int INTERIOR_DISTANCE = 3;
+// Longest cycle looked for
+const int MAX_PERIOD = 64;
+// How close an orbit has to come back to where it was to count as a cycle
+const float CYCLE_TOLERANCE = 1.0e-4;
+// Newton steps taken towards the exact point on a cycle before estimating the interior distance from it
+const int NEWTON_STEPS = 4;
+const float PI = 3.14159265;
+
+// Period of the attracting cycle the orbit through z settled into, or 0 if it doesn't come back round within
+// MAX_PERIOD iterations, with the cycle's multiplier, the derivative of the whole cycle at z
+int find_cycle(vec2 z, vec2 offset, out vec2 multiplier) {
+    vec2 start = z;
+    multiplier = vec2(1.0, 0.0);
+    for (int period = 1; period <= MAX_PERIOD; period++) {
+        multiplier = derivative(z, multiplier, formula);
+        z = iterate(z, formula) + offset;
+        if (distance(z, start) < CYCLE_TOLERANCE) return period;
+    }
+    return 0;
+}
+
+// Distance in the plane from c to the edge of the Mandelbrot set, given a point z near the cycle of this period that
+// its orbit settled into, or -1 if it can't be estimated
+// This is the interior distance estimate of Dolotin and Morozov, which needs the derivatives of the cycle with respect
+// to z and c and their second derivatives, so it's only for z^2 + c
+float interior_distance(vec2 z, vec2 c, int period) {
+    if (julia || formula != MANDELBROT || power != 2.0) return -1.0;
+    // Newton's method on f^period(z) - z = 0 finds the point on the cycle exactly
+    for (int i = 0; i < NEWTON_STEPS; i++) {
+        vec2 w = z;
+        vec2 dw = vec2(1.0, 0.0);
+        for (int n = 0; n < period; n++) {
+            dw = 2.0 * cmul(w, dw);
+            w = square(w) + c;
+        }
+        z -= cdiv(w - z, dw - vec2(1.0, 0.0));
+    }
+    vec2 w = z;
+    vec2 dz = vec2(1.0, 0.0);
+    vec2 dc = vec2(0.0);
+    vec2 dzdz = vec2(0.0);
+    vec2 dzdc = vec2(0.0);
+    for (int n = 0; n < period; n++) {
+        dzdz = 2.0 * (cmul(dz, dz) + cmul(w, dzdz));
+        dzdc = 2.0 * (cmul(dz, dc) + cmul(w, dzdc));
+        dz = 2.0 * cmul(w, dz);
+        dc = 2.0 * cmul(w, dc) + vec2(1.0, 0.0);
+        w = square(w) + c;
     }
-    return 1.0;
+    return (1.0 - dot(dz, dz)) / length(dzdc + cdiv(cmul(dzdz, dc), vec2(1.0, 0.0) - dz));
 }
 
-float tenary(bool predicate, float if_true, float if_false) {
-    if (predicate) return if_true;
-    return if_false;
+// Output for a point whose orbit didn't escape, ending at z, in interior mode
+// Period colouring has the period in y, or 0 if no cycle was found
+// Multiplier colouring has the angle of the multiplier from 0 to 1 in y and its magnitude in w, or -1 if there's no
+// cycle
+// Interior distance colouring has the distance in pixels in w, or -1 if there isn't one
+vec4 inside(vec2 z, vec2 offset, float pixel_size) {
+    vec2 multiplier;
+    int period = find_cycle(z, offset, multiplier);
+    if (period == 0) return vec4(1.0, 0.0, 0.0, -1.0);
+    if (interior == PERIOD) return vec4(1.0, float(period), 0.0, -1.0);
+    if (interior == MULTIPLIER) {
+        float angle = atan(multiplier.y, multiplier.x) / (2.0 * PI);
+        return vec4(1.0, fract(angle), 0.0, length(multiplier));
+    }
+    float estimate = interior_distance(z, offset, period);
+    return vec4(1.0, 0.0, 0.0, estimate < 0.0 ? -1.0 : estimate / pixel_size);
 }
 
 void main() {
-    float ratio = pixel((vec3(vPos.x, vPos.y, 1.0) * transform).xy);
-    float hue = mod(pow(ratio * 360.0, 1.5), 360.0);
-    float saturation = 100.0;
-    float value = ratio * 100.0;
-    float chroma = value * saturation;
-    float x = chroma * (1.0 - abs(mod(hue / (60.0 / 360.0), 2.0) - 1.0));
-    float m = value - chroma;
-    outColor = vec4(
-        tenary(
-            hue < 60.0 || hue >= 300.0,
-            chroma,
-            tenary(
-                hue < 120.0 || hue >= 240.0,
-                x,
-                0.0
-            )
-        ) + m,
-        tenary(
-            hue < 60.0 || (hue >= 180.0 && hue < 240.0),
-            x,
-            tenary(
-                hue < 180.0,
-                chroma,
-                0.0
-            )
-        ) + m,
-        tenary(
-            hue < 120.0,
-            0.0,
-            tenary(
-                hue < 180.0 || hue >= 300.0,
-                x,
-                chroma
-            )
-        ) + m,
-        1.0
-    );
+    vec2 pos = (vec3(vPos.x, vPos.y, 1.0) * transform).xy;
+    // Size of a pixel in the plane, to measure distances in pixels
+    float pixel_size = length(dFdx(pos));
+    vec2 z;
+    vec2 result = pixel(pos, z);
+#if !defined(CUSTOM_FORMULA) && !defined(HYBRID_FORMULA)
+    // Cycles are only looked for with the built-in formulas
+    if (result.x >= 1.0 && interior != FLAT) {
+        outValue = inside(z, julia ? c : pos, pixel_size);
+        return;
+    }
+#endif
+    outValue = vec4(result.x, 0.0, 0.0, result.y < 0.0 ? -1.0 : result.y / pixel_size);
 }
\ No newline at end of file
@@ -0,0 +1,107 @@
+/// Rectangle of the framebuffer as `[x, y, width, height]` in pixels
+pub type Tile = [i32; 4];
+
+/// Frame interval to aim for while a render is spread over several frames
+const TARGET_FRAME_MS: f64 = 20.0;
+
+const MIN_WORK: f64 = 1.0e6;
+const MAX_WORK: f64 = 1.0e11;
+const MIN_TILE_SIZE: i32 = 16;
+const MAX_TILE_SIZE: i32 = 4096;
+
+/// Smallest fraction of the canvas size to render previews at while the view is moving
+const MIN_RESOLUTION: f64 = 0.125;
+
+/// Splits a `width` by `height` framebuffer into square tiles, ordered from the middle outwards
+pub fn split(width: i32, height: i32, size: i32) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(size as usize) {
+        for x in (0..width).step_by(size as usize) {
+            tiles.push([x, y, size.min(width - x), size.min(height - y)]);
+        }
+    }
+    let distance = |[x, y, w, h]: &Tile| {
+        let dx = (2 * x + w - width) as i64;
+        let dy = (2 * y + h - height) as i64;
+        dx * dx + dy * dy
+    };
+    tiles.sort_by_key(distance);
+    tiles
+}
+
+/// How much work the GPU is given per frame, in pixels times iterations, adapted to the frame interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileBudget {
+    pub work: f64,
+}
+
+impl Default for TileBudget {
+    fn default() -> Self {
+        Self { work: 2.0e8 }
+    }
+}
+
+impl TileBudget {
+    /// Side of a square tile which takes about a frame's work at `depth`
+    pub fn tile_size(&self, depth: u32) -> i32 {
+        ((self.work / depth.max(1) as f64).sqrt() as i32).clamp(MIN_TILE_SIZE, MAX_TILE_SIZE)
+    }
+
+    /// Fraction of the canvas size to render `pixels` at while the view is moving, so a frame fits in the budget
+    ///
+    /// Rounded to eighths so the buffers aren't reallocated on every frame
+    pub fn resolution(&self, pixels: f64, depth: u32) -> f64 {
+        let resolution = (self.work / (pixels * depth.max(1) as f64)).sqrt();
+        ((resolution * 8.0).floor() / 8.0).clamp(MIN_RESOLUTION, 1.0)
+    }
+
+    /// Shrinks the budget if the last frame took too long, or grows it if there was time to spare
+    pub fn update(&mut self, frame_ms: f64) {
+        let factor = if frame_ms > TARGET_FRAME_MS * 1.5 {
+            0.7
+        } else if frame_ms < TARGET_FRAME_MS {
+            1.1
+        } else {
+            1.0
+        };
+        self.work = (self.work * factor).clamp(MIN_WORK, MAX_WORK);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tiles::{split, TileBudget};
+
+    #[test]
+    fn split_covers_framebuffer() {
+        let tiles = split(100, 70, 32);
+        assert_eq!(tiles.len(), 4 * 3);
+        let area: i32 = tiles.iter().map(|[_, _, w, h]| w * h).sum();
+        assert_eq!(area, 100 * 70);
+        assert!(tiles
+            .iter()
+            .all(|&[x, y, w, h]| x + w <= 100 && y + h <= 70));
+        assert_eq!(tiles[0], [32, 32, 32, 32]);
+    }
+
+    #[test]
+    fn budget_follows_frame_time() {
+        let mut budget = TileBudget::default();
+        let size = budget.tile_size(1000);
+        budget.update(100.0);
+        assert!(budget.tile_size(1000) < size);
+        for _ in 0..100 {
+            budget.update(5.0);
+        }
+        assert!(budget.tile_size(1000) > size);
+        assert!(budget.tile_size(1) <= 4096);
+    }
+
+    #[test]
+    fn preview_resolution() {
+        let budget = TileBudget { work: 1.0e8 };
+        assert_eq!(budget.resolution(1.0e5, 100), 1.0);
+        assert_eq!(budget.resolution(1.0e6, 400), 0.5);
+        assert_eq!(budget.resolution(1.0e6, 1 << 20), 0.125);
+    }
+}
@@ -45,6 +45,29 @@ pub fn request_animation_frame<Args: 'static>(
     Ok(())
 }
 
+/// Calls the provided callback with the provided arguments after `timeout_ms` milliseconds
+///
+/// Returns the handle to pass to `clearTimeout` to cancel it
+pub fn set_timeout<Args: 'static>(
+    args: Args,
+    window: &Window,
+    timeout_ms: i32,
+    callback: impl Fn(&Args) -> Result<(), JsValue> + 'static,
+) -> Result<i32, JsValue> {
+    let closure =
+        Closure::<dyn Fn() -> Result<(), JsValue>>::new(move || -> Result<(), JsValue> {
+            callback(&args)
+        });
+    let handle = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+        closure.as_ref().unchecked_ref(),
+        timeout_ms,
+    )?;
+    closure.forget();
+    Ok(handle)
+}
+
+type AnimationClosure = Rc<RefCell<Option<Closure<dyn Fn() -> Result<(), JsValue>>>>>;
+
 /// Starts an animation loop with the provided callback and arguments
 ///
 /// If the callback returns false, the loop will terminate
@@ -53,8 +76,7 @@ pub fn start_animation_loop<Args: 'static>(
     window: &Window,
     callback: impl Fn(&Args) -> Result<bool, JsValue> + 'static,
 ) -> Result<(), JsValue> {
-    let f: Rc<RefCell<Option<Closure<dyn Fn() -> Result<(), JsValue>>>>> =
-        Rc::new(RefCell::new(None));
+    let f: AnimationClosure = Rc::new(RefCell::new(None));
     let g = f.clone();
     let window_copy = window.clone();
     *g.borrow_mut() = Some(Closure::<dyn Fn() -> Result<(), JsValue>>::new(
@@ -0,0 +1,159 @@
+use crate::{
+    fixed::{precision_for_scale, Fixed},
+    matrix::Matrix3,
+};
+
+/// Smallest scale the view can be zoomed in to, well above where [`f64`] runs out of exponent
+pub const MIN_SCALE: f64 = 1.0e-300;
+/// Largest scale the view can be zoomed out to
+pub const MAX_SCALE: f64 = 1.0e3;
+
+/// Authoritative position of the camera over the complex plane
+///
+/// Screen points are given in clip space, `(-1, -1)` to `(1, 1)` with y pointing up, and `aspect` is height / width
+#[derive(Clone, Debug, PartialEq)]
+pub struct View {
+    /// Point in the complex plane at the middle of the screen, kept to as much precision as `scale` needs
+    pub center: (Fixed, Fixed),
+    /// Distance in the complex plane from the middle of the screen to its left and right edges
+    pub scale: f64,
+    /// Anticlockwise rotation of the complex plane in radians
+    pub rotation: f64,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            center: Default::default(),
+            scale: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+impl View {
+    /// Offset of a screen point from the middle of the screen in the complex plane, in units of `scale`
+    fn offset(&self, (x, y): (f64, f64), aspect: f64) -> (f64, f64) {
+        let (sin, cos) = self.rotation.sin_cos();
+        let y = y * aspect;
+        (x * cos - y * sin, x * sin + y * cos)
+    }
+
+    /// Point in the complex plane under a screen point, rounded to an [`f64`]
+    pub fn point_at(&self, point: (f64, f64), aspect: f64) -> (f64, f64) {
+        let (x, y) = self.offset(point, aspect);
+        (
+            self.center.0.to_f64() + x * self.scale,
+            self.center.1.to_f64() + y * self.scale,
+        )
+    }
+
+    fn translate(&mut self, (x, y): (f64, f64)) {
+        let precision = precision_for_scale(self.scale);
+        let (re, im) = &self.center;
+        self.center = (
+            &re.clone().with_precision(precision) + &Fixed::from_f64(x, precision),
+            &im.clone().with_precision(precision) + &Fixed::from_f64(y, precision),
+        );
+    }
+
+    /// Moves the camera by a distance measured on the screen
+    pub fn pan(&mut self, delta: (f64, f64), aspect: f64) {
+        let (x, y) = self.offset(delta, aspect);
+        self.translate((x * self.scale, y * self.scale));
+    }
+
+    /// Multiplies the scale by `factor` while keeping the point under `point` in place, stopping at [`MIN_SCALE`] and
+    /// [`MAX_SCALE`]
+    pub fn zoom_at(&mut self, point: (f64, f64), factor: f64, aspect: f64) {
+        let (x, y) = self.offset(point, aspect);
+        let scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        let distance = self.scale - scale;
+        self.scale = scale;
+        self.translate((x * distance, y * distance));
+    }
+
+    /// Rotates the camera anticlockwise by `angle` radians while keeping the point under `point` in place
+    pub fn rotate_at(&mut self, point: (f64, f64), angle: f64, aspect: f64) {
+        let (x, y) = self.offset(point, aspect);
+        self.rotation = (self.rotation + angle) % std::f64::consts::TAU;
+        let (new_x, new_y) = self.offset(point, aspect);
+        self.translate(((x - new_x) * self.scale, (y - new_y) * self.scale));
+    }
+
+    /// Transform from screen space to offsets from `center`, in units of `scale`
+    pub fn screen_transform(&self, aspect: f64) -> Matrix3 {
+        Matrix3::rotate(self.rotation as f32) * Matrix3::scale(1.0, aspect as f32)
+    }
+
+    /// Single precision transform from screen space to the complex plane
+    pub fn transform(&self, aspect: f64) -> Matrix3 {
+        let scale = self.scale as f32;
+        Matrix3::translate(self.center.0.to_f64() as f32, self.center.1.to_f64() as f32)
+            * Matrix3::scale(scale, scale)
+            * self.screen_transform(aspect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::view::{View, MAX_SCALE, MIN_SCALE};
+
+    fn assert_close(a: (f64, f64), b: (f64, f64)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-12 && (a.1 - b.1).abs() < 1e-12,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn zoom_keeps_point_fixed() {
+        let mut view = View::default();
+        view.pan((0.3, -0.2), 0.5);
+        view.rotate_at((0.0, 0.0), 0.7, 0.5);
+        let before = view.point_at((0.25, 0.5), 0.5);
+        view.zoom_at((0.25, 0.5), 0.1, 0.5);
+        assert_close(view.point_at((0.25, 0.5), 0.5), before);
+        view.rotate_at((0.25, 0.5), -1.3, 0.5);
+        assert_close(view.point_at((0.25, 0.5), 0.5), before);
+    }
+
+    #[test]
+    fn pan_follows_rotation() {
+        let mut view = View::default();
+        view.rotate_at((0.0, 0.0), std::f64::consts::FRAC_PI_2, 1.0);
+        view.pan((1.0, 0.0), 1.0);
+        assert_close(view.point_at((0.0, 0.0), 1.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn no_drift() {
+        let mut view = View::default();
+        view.pan((-0.75, 0.1), 1.0);
+        let center = view.center.clone();
+        for _ in 0..200 {
+            view.zoom_at((0.0, 0.0), 0.5, 1.0);
+        }
+        for _ in 0..1000 {
+            view.pan((0.5, 0.25), 1.0);
+            view.pan((-0.5, -0.25), 1.0);
+        }
+        assert!((&view.center.0 - &center.0).is_zero());
+        assert!((&view.center.1 - &center.1).is_zero());
+        assert_eq!(view.scale, 0.5_f64.powi(200));
+    }
+
+    #[test]
+    fn zoom_is_bounded() {
+        let mut view = View::default();
+        for _ in 0..2000 {
+            view.zoom_at((0.5, 0.5), 0.5, 1.0);
+        }
+        assert_eq!(view.scale, MIN_SCALE);
+        for _ in 0..2000 {
+            view.zoom_at((0.5, 0.5), 2.0, 1.0);
+        }
+        assert_eq!(view.scale, MAX_SCALE);
+        assert!(view.point_at((0.5, 0.5), 1.0).0.is_finite());
+    }
+}
@@ -1,7 +1,7 @@
 use std::{cell::RefCell, rc::Rc};
 
 use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{Event, KeyboardEvent};
+use web_sys::{Event, HtmlInputElement, KeyboardEvent};
 
 use crate::App;
 
@@ -15,14 +15,52 @@ pub struct KeysHeld {
     pub down: bool,
     pub plus: bool,
     pub minus: bool,
+    pub rotate_left: bool,
+    pub rotate_right: bool,
 }
 
 impl App {
     pub fn key_down_listener(app_ref: Rc<RefCell<App>>) -> impl Fn(Event) -> Result<(), JsValue> {
         move |event_raw| -> Result<(), JsValue> {
             let event: KeyboardEvent = event_raw.dyn_into().unwrap();
+            // Typing a formula shouldn't move the view
+            if event
+                .target()
+                .is_some_and(|target| target.has_type::<HtmlInputElement>())
             {
-                let keys_held = &mut app_ref.borrow_mut().viewport.keys_held;
+                return Ok(());
+            }
+            match event.key().as_str() {
+                "." | ">" => app_ref.borrow_mut().multiply_depth(2.0),
+                "," | "<" => app_ref.borrow_mut().multiply_depth(0.5),
+                "a" | "A" => app_ref.borrow_mut().toggle_auto_depth(),
+                "j" | "J" => app_ref.borrow_mut().toggle_julia(),
+                "p" => app_ref.borrow_mut().change_power(1.0),
+                "o" => app_ref.borrow_mut().change_power(-1.0),
+                "P" => app_ref.borrow_mut().change_power(0.1),
+                "O" => app_ref.borrow_mut().change_power(-0.1),
+                "f" => app_ref.borrow_mut().cycle_formula(1),
+                "F" => app_ref.borrow_mut().cycle_formula(-1),
+                "b" | "B" => app_ref.borrow_mut().cycle_buddhabrot(),
+                "s" | "S" => app_ref.borrow_mut().toggle_split(),
+                "m" | "M" => app_ref.borrow_mut().cycle_shape(),
+                "c" | "C" => app_ref.borrow_mut().toggle_smooth(),
+                "l" | "L" => app_ref.borrow_mut().toggle_distance_estimation(),
+                "t" => app_ref.borrow_mut().multiply_line_thickness(1.25),
+                "T" => app_ref.borrow_mut().multiply_line_thickness(0.8),
+                "i" => app_ref.borrow_mut().cycle_interior(1),
+                "I" => app_ref.borrow_mut().cycle_interior(-1),
+                "k" => app_ref.borrow_mut().cycle_palette(1),
+                "K" => app_ref.borrow_mut().cycle_palette(-1),
+                "e" => app_ref.borrow_mut().multiply_exposure(1.25),
+                "E" => app_ref.borrow_mut().multiply_exposure(0.8),
+                "g" => app_ref.borrow_mut().change_gamma(0.1),
+                "G" => app_ref.borrow_mut().change_gamma(-0.1),
+                _ => {}
+            }
+            {
+                let mut app = app_ref.borrow_mut();
+                let keys_held = &mut app.focused().keys_held;
                 match event.key().as_str() {
                     "ArrowUp" => keys_held.up = true,
                     "ArrowDown" => keys_held.down = true,
@@ -30,6 +68,8 @@ impl App {
                     "ArrowRight" => keys_held.right = true,
                     "=" | "+" => keys_held.plus = true,
                     "-" | "_" => keys_held.minus = true,
+                    "[" | "{" => keys_held.rotate_left = true,
+                    "]" | "}" => keys_held.rotate_right = true,
                     "Shift" => keys_held.shift = true,
                     "Control" => keys_held.ctrl = true,
                     _ => {}
@@ -44,7 +84,7 @@ impl App {
         move |event_raw| -> Result<(), JsValue> {
             let event: KeyboardEvent = event_raw.dyn_into().unwrap();
             let mut app = app_ref.borrow_mut();
-            let keys_held = &mut app.viewport.keys_held;
+            let keys_held = &mut app.focused().keys_held;
             match event.key().as_str() {
                 "ArrowUp" => keys_held.up = false,
                 "ArrowDown" => keys_held.down = false,
@@ -52,6 +92,8 @@ impl App {
                 "ArrowRight" => keys_held.right = false,
                 "=" | "+" => keys_held.plus = false,
                 "-" | "_" => keys_held.minus = false,
+                "[" | "{" => keys_held.rotate_left = false,
+                "]" | "}" => keys_held.rotate_right = false,
                 "Shift" => keys_held.shift = false,
                 "Control" => keys_held.ctrl = false,
                 _ => {}
@@ -5,36 +5,222 @@ use pointer::Pointer;
 use wasm_bindgen::JsValue;
 use web_sys::Event;
 
-use crate::{matrix::Matrix3, util::add_event_listener, App};
+use crate::{
+    fixed::Fixed, matrix::Matrix3, raymarch::Camera, shader::resize_buffers,
+    util::add_event_listener, view::View, App,
+};
 
 mod keyboard;
 mod pointer;
 mod wheel;
 mod window;
 
-#[derive(Debug, Clone, Default)]
+/// Which set is being drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Mandelbrot,
+    Julia,
+}
+
+/// Part of the canvas with its own [`ViewportController`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pane {
+    /// The whole canvas, or the Mandelbrot set on the left in split view
+    #[default]
+    Main,
+    /// Julia set for the point under the pointer on the right in split view
+    Julia,
+}
+
+impl Pane {
+    /// Region of the canvas the pane covers, see [`crate::shader::set_region`]
+    pub fn region(self, split: bool) -> [f64; 4] {
+        match (split, self) {
+            (false, _) => [0.0, 0.0, 1.0, 1.0],
+            (true, Pane::Main) => [0.0, 0.0, 0.5, 1.0],
+            (true, Pane::Julia) => [0.5, 0.0, 0.5, 1.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ViewportController {
     keys_held: KeysHeld,
     pointers: Vec<Pointer>,
     last_frame_ms: f32,
-    viewport_transform: Matrix3,
-    window_transform: Matrix3,
+    mode: Mode,
+    view: View,
+    /// View of the other mode, restored when switching back to it
+    other_view: View,
+    /// Height / width of the canvas
+    aspect: f64,
+    /// Orbit camera which takes over panning, zooming and rotating while a 3D shape is drawn
+    camera: Option<Camera>,
+}
+
+impl Default for ViewportController {
+    fn default() -> Self {
+        Self {
+            keys_held: Default::default(),
+            pointers: Default::default(),
+            last_frame_ms: 0.0,
+            mode: Default::default(),
+            view: Default::default(),
+            other_view: View {
+                scale: 1.6,
+                ..Default::default()
+            },
+            aspect: 1.0,
+            camera: None,
+        }
+    }
 }
 
 impl ViewportController {
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Switches between the Mandelbrot and Julia sets, each keeping its own view
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            Mode::Mandelbrot => Mode::Julia,
+            Mode::Julia => Mode::Mandelbrot,
+        };
+        std::mem::swap(&mut self.view, &mut self.other_view);
+    }
+
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    /// Height / width of the canvas
+    pub fn aspect(&self) -> f64 {
+        self.aspect
+    }
+
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+    }
+
+    /// Point in the complex plane under a screen point
+    pub fn point_at(&self, point: (f64, f64)) -> (f64, f64) {
+        self.view.point_at(point, self.aspect)
+    }
+
+    /// Single precision transform from screen space to the complex plane
     pub fn transform(&self) -> Matrix3 {
-        self.viewport_transform * self.window_transform
+        self.view.transform(self.aspect)
+    }
+
+    /// Transform from screen space to offsets from [`ViewportController::center`], in units of [`ViewportController::scale`]
+    pub fn screen_transform(&self) -> Matrix3 {
+        self.view.screen_transform(self.aspect)
+    }
+
+    pub fn center(&self) -> &(Fixed, Fixed) {
+        &self.view.center
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.view.scale
+    }
+
+    pub fn camera(&self) -> Option<&Camera> {
+        self.camera.as_ref()
+    }
+
+    pub fn set_camera(&mut self, camera: Option<Camera>) {
+        self.camera = camera;
+    }
+
+    /// Moves the view, or swings the camera around the shape in 3D
+    pub fn pan(&mut self, delta: (f64, f64)) {
+        match &mut self.camera {
+            Some(camera) => camera.orbit(delta),
+            None => self.view.pan(delta, self.aspect),
+        }
+    }
+
+    /// Zooms in around `point`, or moves the camera towards the shape in 3D
+    pub fn zoom_at(&mut self, point: (f64, f64), factor: f64) {
+        match &mut self.camera {
+            Some(camera) => camera.zoom(factor),
+            None => self.view.zoom_at(point, factor, self.aspect),
+        }
+    }
+
+    /// Rotates the view around `point`, or swings the camera around the vertical axis in 3D
+    pub fn rotate_at(&mut self, point: (f64, f64), angle: f64) {
+        match &mut self.camera {
+            Some(camera) => camera.orbit((angle / std::f64::consts::FRAC_PI_2, 0.0)),
+            None => self.view.rotate_at(point, angle, self.aspect),
+        }
     }
 }
 
 impl App {
+    /// Controller of `pane`, where both share the main one outside of split view
+    pub fn pane(&self, pane: Pane) -> &ViewportController {
+        match (pane, &self.julia_viewport) {
+            (Pane::Julia, Some(viewport)) => viewport,
+            _ => &self.viewport,
+        }
+    }
+
+    pub fn pane_mut(&mut self, pane: Pane) -> &mut ViewportController {
+        match (pane, &mut self.julia_viewport) {
+            (Pane::Julia, Some(viewport)) => viewport,
+            _ => &mut self.viewport,
+        }
+    }
+
+    /// Controller of the pane under the pointer, which all input goes to
+    pub fn focused(&mut self) -> &mut ViewportController {
+        self.pane_mut(self.focus)
+    }
+
+    /// Focuses the pane under a point on the canvas in clip space, unless a pointer is held down in the focused one
+    ///
+    /// Held keys move over to the newly focused pane
+    fn update_focus(&mut self, (x, _): (f32, f32)) {
+        if self.julia_viewport.is_none()
+            || self
+                .focused()
+                .pointers
+                .iter()
+                .any(|pointer| pointer.down_position.is_some())
+        {
+            return;
+        }
+        let pane = if x < 0.0 { Pane::Main } else { Pane::Julia };
+        if pane != self.focus {
+            let keys_held = std::mem::take(&mut self.focused().keys_held);
+            self.focus = pane;
+            self.focused().keys_held = keys_held;
+        }
+    }
+
+    /// Converts a point on the canvas in clip space to the clip space of the focused pane
+    fn pane_position(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        match (self.julia_viewport.is_some(), self.focus) {
+            (false, _) => (x, y),
+            (true, Pane::Main) => (x * 2.0 + 1.0, y),
+            (true, Pane::Julia) => (x * 2.0 - 1.0, y),
+        }
+    }
+
     pub fn release_all_listener(
         app_ref: Rc<RefCell<App>>,
     ) -> impl FnMut(Event) -> Result<(), JsValue> {
         move |_| {
             let mut app = app_ref.borrow_mut();
-            app.viewport.keys_held = Default::default();
-            app.viewport.pointers = Vec::new();
+            for pane in [Pane::Main, Pane::Julia] {
+                let viewport = app.pane_mut(pane);
+                viewport.keys_held = Default::default();
+                viewport.pointers = Vec::new();
+            }
             app.stop_animation();
             Ok(())
         }
@@ -124,8 +310,16 @@ impl App {
         self.canvas.set_height(height as u32);
 
         self.context.viewport(0, 0, width, height);
+        resize_buffers(&self.context, &mut self.props, width, height);
 
-        // Avoid stretching viewport
-        self.viewport.window_transform = Matrix3::scale(1.0, height as f32 / width as f32);
+        // Avoid stretching viewport, where each pane gets half of the width in split view
+        let aspect = height as f64 / width as f64;
+        match &mut self.julia_viewport {
+            Some(julia_viewport) => {
+                self.viewport.aspect = aspect * 2.0;
+                julia_viewport.aspect = aspect * 2.0;
+            }
+            None => self.viewport.aspect = aspect,
+        }
     }
 }
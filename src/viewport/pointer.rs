@@ -3,7 +3,10 @@ use std::{cell::RefCell, rc::Rc};
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{Event, PointerEvent};
 
-use crate::{matrix::Matrix3, App};
+use crate::App;
+
+/// Furthest a pointer can move in clip space between going down and up for it to count as a click
+const CLICK_DISTANCE: f32 = 0.01;
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Pointer {
@@ -21,14 +24,17 @@ impl App {
         going_up: bool,
     ) -> (&'a mut Pointer, (f32, f32)) {
         let id = event.pointer_id();
-        let new_pos = self.extract_pos(event);
-        for (index, pointer) in self.viewport.pointers.iter().enumerate() {
+        let canvas_pos = self.extract_pos(event);
+        self.update_focus(canvas_pos);
+        let new_pos = self.pane_position(canvas_pos);
+        let viewport = self.focused();
+        for (index, pointer) in viewport.pointers.iter().enumerate() {
             if pointer.id == id {
                 let delta = (
                     new_pos.0 - pointer.position.0,
                     new_pos.1 - pointer.position.1,
                 );
-                let pointer_mut = self.viewport.pointers.get_mut(index).unwrap();
+                let pointer_mut = viewport.pointers.get_mut(index).unwrap();
                 pointer_mut.position = new_pos;
                 if going_down {
                     pointer_mut.down_position = Some(new_pos);
@@ -38,23 +44,24 @@ impl App {
                 return (pointer_mut, delta);
             }
         }
-        self.viewport.pointers.push(Pointer {
+        viewport.pointers.push(Pointer {
             id,
             position: new_pos,
             down_position: if going_down { Some(new_pos) } else { None },
         });
-        (self.viewport.pointers.last_mut().unwrap(), (0.0, 0.0))
+        (viewport.pointers.last_mut().unwrap(), (0.0, 0.0))
     }
 
+    /// Position of the pointer on the canvas in clip space, with y pointing up
     fn extract_pos(&mut self, event: &PointerEvent) -> (f32, f32) {
         let rect = self.canvas.get_bounding_client_rect();
         (
             (event.client_x() as f32 - rect.left() as f32) / self.canvas.client_width() as f32
                 * 2.0
                 - 1.0,
-            (event.client_y() as f32 - rect.top() as f32) / self.canvas.client_height() as f32
-                * 2.0
-                - 1.0,
+            1.0 - (event.client_y() as f32 - rect.top() as f32)
+                / self.canvas.client_height() as f32
+                * 2.0,
         )
     }
 
@@ -65,33 +72,39 @@ impl App {
             let event: PointerEvent = event_raw.dyn_into().unwrap();
             let mut app = app_ref.borrow_mut();
             let (pointer, (delta_x, delta_y)) = app.init_pointer(&event, false, false);
-            if let Some((pointer_x, pointer_y)) = pointer.down_position {
-                if app.viewport.pointers.len() == 1 {
+            if pointer.down_position.is_none() {
+                let position = (pointer.position.0 as f64, pointer.position.1 as f64);
+                app.hover(position);
+            } else {
+                let id = pointer.id;
+                let (x, y) = (pointer.position.0 as f64, pointer.position.1 as f64);
+                let (delta_x, delta_y) = (delta_x as f64, delta_y as f64);
+                let anchor = app
+                    .focused()
+                    .pointers
+                    .iter()
+                    .find(|other| other.id != id && other.down_position.is_some())
+                    .map(|other| (other.position.0 as f64, other.position.1 as f64));
+                match anchor {
                     // Pan
-                    app.viewport.viewport_transform *=
-                        Matrix3([1.0, 0.0, -delta_x, 0.0, 1.0, delta_y, 0.0, 0.0, 1.0]);
-                } else {
-                    // Touchscreen: scale around midpoint by applying affine transform
-                    let (xs, ys): (Vec<f32>, Vec<f32>) = app
-                        .viewport
-                        .pointers
-                        .iter()
-                        .filter_map(|pointer| pointer.down_position)
-                        .unzip();
-                    let midpoint_x = xs.iter().sum::<f32>() / xs.len() as f32;
-                    let midpoint_y = ys.iter().sum::<f32>() / ys.len() as f32;
-                    let i = delta_x * (pointer_x - midpoint_x).signum();
-                    let j = delta_y * (pointer_y - midpoint_y).signum();
-                    let factor =
-                        1.0 - (i.powf(2.0) + j.powf(2.0)).powf(0.5) * i.signum() * j.signum();
-                    app.viewport.viewport_transform *=
-                        Matrix3([1.0, 0.0, midpoint_x, 0.0, 1.0, midpoint_y, 0.0, 0.0, 1.0]);
-                    app.viewport.viewport_transform *=
-                        Matrix3([factor, 0.0, 0.0, 0.0, factor, 0.0, 0.0, 0.0, 1.0]);
-                    app.viewport.viewport_transform *=
-                        Matrix3([1.0, 0.0, -midpoint_x, 0.0, 1.0, -midpoint_y, 0.0, 0.0, 1.0]);
+                    None => app.focused().pan((-delta_x, -delta_y)),
+                    // Touchscreen: scale and rotate around the other pointer so the view stays under both
+                    Some((anchor_x, anchor_y)) => {
+                        let viewport = app.focused();
+                        let aspect = viewport.aspect;
+                        let old = (x - delta_x - anchor_x, (y - delta_y - anchor_y) * aspect);
+                        let new = (x - anchor_x, (y - anchor_y) * aspect);
+                        let new_length = new.0.hypot(new.1);
+                        if new_length > 0.0 {
+                            viewport.zoom_at((anchor_x, anchor_y), old.0.hypot(old.1) / new_length);
+                            viewport.rotate_at(
+                                (anchor_x, anchor_y),
+                                old.1.atan2(old.0) - new.1.atan2(new.0),
+                            );
+                        }
+                    }
                 }
-                app.draw();
+                app.draw_interactive();
             }
             Ok(())
         }
@@ -113,7 +126,15 @@ impl App {
         move |event_raw| -> Result<(), JsValue> {
             let event: PointerEvent = event_raw.dyn_into().unwrap();
             let mut app = app_ref.borrow_mut();
+            let (pointer, _) = app.init_pointer(&event, false, false);
+            let (position, down_position) = (pointer.position, pointer.down_position);
             app.init_pointer(&event, false, true);
+            // A click rather than the end of a drag
+            if down_position
+                .is_some_and(|(x, y)| (position.0 - x).hypot(position.1 - y) < CLICK_DISTANCE)
+            {
+                app.pick_julia_c((position.0 as f64, position.1 as f64));
+            }
             Ok(())
         }
     }
@@ -3,7 +3,7 @@ use std::{cell::RefCell, rc::Rc};
 use wasm_bindgen::JsValue;
 use web_sys::Event;
 
-use crate::{matrix::Matrix3, App};
+use crate::App;
 
 impl App {
     pub fn resize_listener(app_ref: Rc<RefCell<App>>) -> impl Fn(Event) -> Result<(), JsValue> {
@@ -17,59 +17,52 @@ impl App {
 
     pub fn animate(&mut self) {
         let now = self.performance.now() as f32;
-        let mul = 100_f32.min(now as f32 - self.viewport.last_frame_ms) / 500.0;
-        self.viewport.last_frame_ms = now;
+        let viewport = self.focused();
+        let mul = 100_f32.min(now - viewport.last_frame_ms) / 500.0;
+        viewport.last_frame_ms = now;
         let mut state_changed = false;
-        if self.viewport.keys_held.plus {
+        if viewport.keys_held.plus {
             // zoom in
-            let m13 = self.viewport.viewport_transform[(0, 2)];
-            let m23 = self.viewport.viewport_transform[(1, 2)];
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, m13, 0.0, 1.0, m23, 0.0, 0.0, 1.0]);
-            self.viewport.viewport_transform *=
-                Matrix3([1.0 - mul, 0.0, 0.0, 0.0, 1.0 - mul, 0.0, 0.0, 0.0, 1.0]);
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, -m13, 0.0, 1.0, -m23, 0.0, 0.0, 1.0]);
+            viewport.zoom_at((0.0, 0.0), 1.0 - mul as f64);
             state_changed = true;
         }
-        if self.viewport.keys_held.minus {
+        if viewport.keys_held.minus {
             // zoom out
-            let m13 = self.viewport.viewport_transform[(0, 2)];
-            let m23 = self.viewport.viewport_transform[(1, 2)];
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, m13, 0.0, 1.0, m23, 0.0, 0.0, 1.0]);
-            self.viewport.viewport_transform *=
-                Matrix3([1.0 + mul, 0.0, 0.0, 0.0, 1.0 + mul, 0.0, 0.0, 0.0, 1.0]);
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, -m13, 0.0, 1.0, -m23, 0.0, 0.0, 1.0]);
+            viewport.zoom_at((0.0, 0.0), 1.0 + mul as f64);
             state_changed = true;
         }
-        if self.viewport.keys_held.left {
+        if viewport.keys_held.left {
             // pan left
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, -mul, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+            viewport.pan((-mul as f64, 0.0));
             state_changed = true;
         }
-        if self.viewport.keys_held.right {
+        if viewport.keys_held.right {
             // pan right
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, mul, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+            viewport.pan((mul as f64, 0.0));
             state_changed = true;
         }
-        if self.viewport.keys_held.up {
+        if viewport.keys_held.up {
             // pan up
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, 0.0, 0.0, 1.0, mul, 0.0, 0.0, 1.0]);
+            viewport.pan((0.0, mul as f64));
             state_changed = true;
         }
-        if self.viewport.keys_held.down {
+        if viewport.keys_held.down {
             // pan down
-            self.viewport.viewport_transform *=
-                Matrix3([1.0, 0.0, 0.0, 0.0, 1.0, -mul, 0.0, 0.0, 1.0]);
+            viewport.pan((0.0, -mul as f64));
+            state_changed = true;
+        }
+        if viewport.keys_held.rotate_left {
+            // rotate anticlockwise
+            viewport.rotate_at((0.0, 0.0), mul as f64);
+            state_changed = true;
+        }
+        if viewport.keys_held.rotate_right {
+            // rotate clockwise
+            viewport.rotate_at((0.0, 0.0), -mul as f64);
             state_changed = true;
         }
         if state_changed {
-            self.draw();
+            self.draw_interactive();
         }
     }
 }